@@ -0,0 +1,85 @@
+use Real;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Aabb {
+    pub min_x: Real,
+    pub min_y: Real,
+    pub max_x: Real,
+    pub max_y: Real,
+}
+
+impl Aabb {
+    pub fn new(min_x: Real, min_y: Real, max_x: Real, max_y: Real) -> Aabb {
+        Aabb {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+        }
+    }
+
+    /// Boxes that only touch along an edge don't intersect, matching `Collidable::collides`
+    /// for shapes with area. Segments collide on contact, and an axis-aligned segment's box
+    /// is flat, so this can reject segment pairs that `collides` accepts.
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min_x < other.max_x
+            && other.min_x < self.max_x
+            && self.min_y < other.max_y
+            && other.min_y < self.max_y
+    }
+
+    /// Points on the boundary count as inside, matching `Collidable::contains_point`.
+    pub fn contains_point(&self, x: Real, y: Real) -> bool {
+        x >= self.min_x && x <= self.max_x && y >= self.min_y && y <= self.max_y
+    }
+
+    /// Distance from the point to the nearest point of the box, 0 inside it.
+    pub fn distance_to_point(&self, x: Real, y: Real) -> Real {
+        let dx = (self.min_x - x).max(x - self.max_x).max(0.);
+        let dy = (self.min_y - y).max(y - self.max_y).max(0.);
+        (dx * dx + dy * dy).sqrt()
+    }
+
+    /// Parameter `t` at which the ray `o + t d` enters the box, or 0 if `o` is already inside.
+    pub fn ray_entry(&self, ox: Real, oy: Real, dx: Real, dy: Real) -> Option<Real> {
+        let mut tmin: Real = 0.;
+        let mut tmax = Real::INFINITY;
+        for &(o, d, min, max) in &[
+            (ox, dx, self.min_x, self.max_x),
+            (oy, dy, self.min_y, self.max_y),
+        ] {
+            if d == 0. {
+                if o < min || o > max {
+                    return None;
+                }
+                continue;
+            }
+            let (t1, t2) = ((min - o) / d, (max - o) / d);
+            tmin = tmin.max(t1.min(t2));
+            tmax = tmax.min(t1.max(t2));
+            if tmin > tmax {
+                return None;
+            }
+        }
+        Some(tmin)
+    }
+
+    pub fn merge(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min_x: self.min_x.min(other.min_x),
+            min_y: self.min_y.min(other.min_y),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+}
+
+impl From<(Real, Real, Real, Real)> for Aabb {
+    fn from((min_x, min_y, max_x, max_y): (Real, Real, Real, Real)) -> Aabb {
+        Aabb::new(min_x, min_y, max_x, max_y)
+    }
+}
@@ -0,0 +1,194 @@
+use aabb::Aabb;
+use Real;
+
+/// A broadphase quadtree over bounding boxes, suited to objects of very different sizes.
+///
+/// A node splits once it holds more than `max_items` and is shallower than `max_depth`.
+/// Boxes that straddle a quadrant boundary, or lie outside the world rect, stay in the
+/// deepest node that fully contains them rather than being duplicated into children.
+#[derive(Clone, Debug)]
+pub struct AabbQuadtree {
+    pub max_depth: usize,
+    pub max_items: usize,
+    root: Node,
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    bounds: Aabb,
+    items: Vec<(usize, Aabb)>,
+    children: Vec<Node>,
+}
+
+fn overlaps(a: &Aabb, b: &Aabb) -> bool {
+    a.min_x <= b.max_x && b.min_x <= a.max_x && a.min_y <= b.max_y && b.min_y <= a.max_y
+}
+
+fn encloses(outer: &Aabb, inner: &Aabb) -> bool {
+    outer.min_x <= inner.min_x
+        && outer.min_y <= inner.min_y
+        && inner.max_x <= outer.max_x
+        && inner.max_y <= outer.max_y
+}
+
+impl Node {
+    fn new(bounds: Aabb) -> Node {
+        Node {
+            bounds,
+            items: vec![],
+            children: vec![],
+        }
+    }
+
+    fn split(&mut self) {
+        let b = self.bounds;
+        let mid_x: Real = (b.min_x + b.max_x) / 2.;
+        let mid_y: Real = (b.min_y + b.max_y) / 2.;
+        self.children = vec![
+            Node::new(Aabb::new(b.min_x, b.min_y, mid_x, mid_y)),
+            Node::new(Aabb::new(mid_x, b.min_y, b.max_x, mid_y)),
+            Node::new(Aabb::new(mid_x, mid_y, b.max_x, b.max_y)),
+            Node::new(Aabb::new(b.min_x, mid_y, mid_x, b.max_y)),
+        ];
+        let items = ::std::mem::take(&mut self.items);
+        for (id, aabb) in items {
+            match self
+                .children
+                .iter_mut()
+                .find(|c| encloses(&c.bounds, &aabb))
+            {
+                Some(child) => child.items.push((id, aabb)),
+                None => self.items.push((id, aabb)),
+            }
+        }
+    }
+
+    fn insert(&mut self, id: usize, aabb: Aabb, depth: usize, max_depth: usize, max_items: usize) {
+        if self.children.is_empty() {
+            self.items.push((id, aabb));
+            if self.items.len() > max_items && depth < max_depth {
+                self.split();
+            }
+            return;
+        }
+        match self
+            .children
+            .iter_mut()
+            .find(|c| encloses(&c.bounds, &aabb))
+        {
+            Some(child) => child.insert(id, aabb, depth + 1, max_depth, max_items),
+            None => self.items.push((id, aabb)),
+        }
+    }
+
+    fn query(&self, region: &Aabb, ids: &mut Vec<usize>) {
+        for &(id, ref aabb) in &self.items {
+            if overlaps(aabb, region) {
+                ids.push(id);
+            }
+        }
+        for child in &self.children {
+            if overlaps(&child.bounds, region) {
+                child.query(region, ids);
+            }
+        }
+    }
+}
+
+impl AabbQuadtree {
+    pub fn new(bounds: Aabb, max_depth: usize, max_items: usize) -> AabbQuadtree {
+        AabbQuadtree {
+            max_depth,
+            max_items,
+            root: Node::new(bounds),
+        }
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.root.bounds
+    }
+
+    pub fn insert(&mut self, id: usize, aabb: Aabb) {
+        let (max_depth, max_items) = (self.max_depth, self.max_items);
+        self.root.insert(id, aabb, 0, max_depth, max_items);
+    }
+
+    /// Ids whose boxes overlap `region`, touching included, in ascending order.
+    pub fn query(&self, region: Aabb) -> Vec<usize> {
+        let mut ids = vec![];
+        self.root.query(&region, &mut ids);
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    /// Empties the tree, keeping the world rect and split policy.
+    pub fn clear(&mut self) {
+        self.root = Node::new(self.root.bounds);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> Real {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (self.0 >> 40) as Real / (1u64 << 24) as Real
+        }
+
+        fn range(&mut self, lo: Real, hi: Real) -> Real {
+            lo + (hi - lo) * self.next()
+        }
+    }
+
+    #[test]
+    fn small_queries_return_exactly_the_nearby_overlaps() {
+        let mut rng = Lcg(3);
+        let mut tree = AabbQuadtree::new(Aabb::new(0., 0., 1000., 1000.), 8, 4);
+        let mut boxes = vec![];
+        for id in 0..3000 {
+            let (x, y) = (rng.range(-50., 1000.), rng.range(-50., 1000.));
+            // Mostly small boxes with the odd large one, and a few poking past the world rect.
+            let size = if id % 50 == 0 {
+                rng.range(100., 300.)
+            } else {
+                rng.range(1., 10.)
+            };
+            let aabb = Aabb::new(x, y, x + size, y + size);
+            tree.insert(id, aabb);
+            boxes.push(aabb);
+        }
+
+        for _ in 0..200 {
+            let (x, y) = (rng.range(0., 1000.), rng.range(0., 1000.));
+            let region = Aabb::new(x, y, x + 15., y + 15.);
+            let expected: Vec<usize> = (0..boxes.len())
+                .filter(|&id| overlaps(&boxes[id], &region))
+                .collect();
+            assert_eq!(tree.query(region), expected);
+        }
+
+        tree.clear();
+        assert!(tree.query(Aabb::new(0., 0., 1000., 1000.)).is_empty());
+    }
+
+    #[test]
+    fn boxes_straddling_the_split_stay_findable() {
+        let mut tree = AabbQuadtree::new(Aabb::new(0., 0., 100., 100.), 4, 1);
+        tree.insert(0, Aabb::new(10., 10., 20., 20.));
+        tree.insert(1, Aabb::new(45., 45., 55., 55.));
+        tree.insert(2, Aabb::new(80., 80., 90., 90.));
+        tree.insert(3, Aabb::new(120., 10., 130., 20.));
+        assert_eq!(tree.query(Aabb::new(49., 49., 51., 51.)), vec![1]);
+        assert_eq!(tree.query(Aabb::new(55., 55., 80., 80.)), vec![1, 2]);
+        assert_eq!(tree.query(Aabb::new(125., 15., 126., 16.)), vec![3]);
+        assert!(tree.query(Aabb::new(30., 70., 40., 80.)).is_empty());
+    }
+}
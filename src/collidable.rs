@@ -2,40 +2,376 @@ extern crate nalgebra;
 
 use nalgebra::{Rotate, Rotation2, Vector1, Vector2};
 
-use std::f32::consts;
+use consts;
+use Real;
 
-use std::cmp::Ordering::Equal;
-use util::{calc_normx, calc_normy};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-#[derive(Copy, Clone, Debug)]
+use std::cmp;
+use std::error;
+use std::fmt;
+use std::mem;
+use std::thread;
+use util::{
+    calc_normx, calc_normy, clip_poly, closest_pnt_ellipse, closest_pnts_seg_seg, convex_hull,
+    decompose_convex_poly, dist_line_pnt, dist_poly_circ, dist_poly_circ_with_face_and_supp,
+    dist_sqrdf, poly_boundary_contains_pnt, poly_centroid, poly_contains_pnt, poly_extents,
+    poly_inertia, poly_is_convex, poly_is_simple, poly_signed_area, project_poly, ray_circle,
+    ray_circle_exit, ray_segment, reflect, segments_cross, segments_intersect, simplify_rdp_ring,
+    sqrf, support_pnt, triangulate_poly, SupportPoint,
+};
+
+const ELLIPSE_SEGMENTS: usize = 32;
+const GJK_ITERATIONS: usize = 32;
+const EPA_ITERATIONS: usize = 64;
+const EPA_TOLERANCE: Real = 1e-4;
+const TOI_ITERATIONS: usize = 32;
+const TOI_TOLERANCE: Real = 1e-4;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum CollidableShape {
     Circle,
     Polygon,
+    Capsule,
+    Segment,
+    Ellipse,
 }
 
-#[derive(Clone, Debug)]
-pub struct Collidable {
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Winding {
+    Clockwise,
+    CounterClockwise,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CollidableError {
+    TooFewVertices,
+    MismatchedLengths,
+    NonFiniteCoordinate,
+    ZeroArea,
+    NegativeSize,
+    MissingShape,
+    MismatchedNormals,
+    StaleNormals,
+    SelfIntersecting,
+}
+
+impl fmt::Display for CollidableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match *self {
+            CollidableError::TooFewVertices => "a polygon needs at least 3 vertices",
+            CollidableError::MismatchedLengths => "vertx and verty must both have nvert entries",
+            CollidableError::NonFiniteCoordinate => "vertex coordinates must be finite",
+            CollidableError::ZeroArea => "the polygon has no area",
+            CollidableError::NegativeSize => "radii and sizes must not be negative",
+            CollidableError::MissingShape => "no shape was given",
+            CollidableError::MismatchedNormals => "normx and normy must both have nvert entries",
+            CollidableError::StaleNormals => "the normals don't match the vertices",
+            CollidableError::SelfIntersecting => "the polygon crosses itself",
+        };
+        f.write_str(msg)
+    }
+}
+
+impl error::Error for CollidableError {}
+
+/// With the `serde` feature the normals and cached bounds aren't serialized; they're
+/// recomputed on load.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "CollidableRepr<T>"))]
+pub struct Collidable<T = ()> {
     pub collidable_type: u8,
     pub collidable_shape: CollidableShape,
     pub collidable_id: usize,
 
-    // CIRCLE
-    pub centrex: f32,
-    pub centrey: f32,
-    pub radius: f32,
-    pub width: f32,
-    pub height: f32,
+    // CIRCLE, ELLIPSE (width and height are the axis diameters)
+    pub centrex: Real,
+    pub centrey: Real,
+    pub radius: Real,
+    pub width: Real,
+    pub height: Real,
 
-    // POLYGON
+    // POLYGON, CAPSULE and SEGMENT (the two endpoints)
     pub nvert: usize,
-    pub vertx: Vec<f32>,
-    pub verty: Vec<f32>,
-    pub normx: Vec<f32>,
-    pub normy: Vec<f32>,
+    pub vertx: Vec<Real>,
+    pub verty: Vec<Real>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub normx: Vec<Real>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub normy: Vec<Real>,
+
+    // ARC (the exact sector behind a `new_arc` polygon, cleared once the outline is edited)
+    pub sector: Option<Sector>,
+
+    // Extra thickness `collides` and `manifold` add around the shape, 0 for none
+    pub margin: Real,
+
+    // Total radians turned by `rotate` and friends, for drawing; collision ignores it
+    pub angle: Real,
+
+    // Anything the user wants to carry along, e.g. an entity handle
+    pub data: T,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    cached_aabb: (Real, Real, Real, Real),
+}
+
+/// The serialized form of a `Collidable`, without the derived normals and bounds.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct CollidableRepr<T> {
+    collidable_type: u8,
+    collidable_shape: CollidableShape,
+    collidable_id: usize,
+    centrex: Real,
+    centrey: Real,
+    radius: Real,
+    width: Real,
+    height: Real,
+    nvert: usize,
+    vertx: Vec<Real>,
+    verty: Vec<Real>,
+    #[serde(default)]
+    sector: Option<Sector>,
+    #[serde(default)]
+    margin: Real,
+    #[serde(default)]
+    angle: Real,
+    #[serde(default)]
+    data: T,
+}
+
+#[cfg(feature = "serde")]
+impl<T> From<CollidableRepr<T>> for Collidable<T> {
+    fn from(repr: CollidableRepr<T>) -> Collidable<T> {
+        let normx = calc_normx(repr.nvert, &repr.verty);
+        let normy = calc_normy(repr.nvert, &repr.vertx);
+        Collidable {
+            collidable_type: repr.collidable_type,
+            collidable_shape: repr.collidable_shape,
+            collidable_id: repr.collidable_id,
+
+            centrex: repr.centrex,
+            centrey: repr.centrey,
+            radius: repr.radius,
+            width: repr.width,
+            height: repr.height,
+
+            nvert: repr.nvert,
+            vertx: repr.vertx,
+            verty: repr.verty,
+            normx,
+            normy,
+            sector: repr.sector,
+            margin: repr.margin,
+            angle: repr.angle,
+            data: repr.data,
+            cached_aabb: (0., 0., 0., 0.),
+        }
+        .with_cached_aabb()
+    }
+}
+
+/// Where a ray first meets a shape. `normal` is unit length and faces back along the ray.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RayHit {
+    pub distance: Real,
+    pub point: (Real, Real),
+    pub normal: (Real, Real),
+}
+
+/// How two colliding shapes touch. `normal` is unit length and points from the first shape
+/// towards the second; moving the first by `-normal * depth` separates them.
+/// `depth` includes both shapes' `margin`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Manifold {
+    pub normal: (Real, Real),
+    pub depth: Real,
+    pub contacts: Vec<(Real, Real)>,
+}
+
+#[derive(Clone, Debug)]
+enum BuilderShape {
+    Circle(Real, Real, Real),
+    Rect(Real, Real, Real, Real),
+    Polygon(Vec<(Real, Real)>),
+}
+
+/// Builds a `Collidable` step by step, validating the geometry in `build`. The type and id
+/// default to 0; the last shape given wins.
+#[derive(Clone, Debug, Default)]
+pub struct CollidableBuilder {
+    collidable_type: u8,
+    collidable_id: usize,
+    shape: Option<BuilderShape>,
+}
+
+impl CollidableBuilder {
+    pub fn new() -> CollidableBuilder {
+        CollidableBuilder::default()
+    }
+
+    pub fn collidable_type(mut self, t: u8) -> CollidableBuilder {
+        self.collidable_type = t;
+        self
+    }
+
+    pub fn id(mut self, i: usize) -> CollidableBuilder {
+        self.collidable_id = i;
+        self
+    }
+
+    pub fn circle(mut self, cx: Real, cy: Real, r: Real) -> CollidableBuilder {
+        self.shape = Some(BuilderShape::Circle(cx, cy, r));
+        self
+    }
+
+    pub fn rect(mut self, x: Real, y: Real, w: Real, h: Real) -> CollidableBuilder {
+        self.shape = Some(BuilderShape::Rect(x, y, w, h));
+        self
+    }
+
+    pub fn polygon(mut self, points: &[(Real, Real)]) -> CollidableBuilder {
+        self.shape = Some(BuilderShape::Polygon(points.to_vec()));
+        self
+    }
+
+    /// Polygons are checked as in `Collidable::try_new_poly`. Rects need a positive width
+    /// and height, circles a radius of at least 0.
+    pub fn build(self) -> Result<Collidable, CollidableError> {
+        let (t, i) = (self.collidable_type, self.collidable_id);
+        match self.shape {
+            None => Err(CollidableError::MissingShape),
+            Some(BuilderShape::Circle(cx, cy, r)) => {
+                if !(cx.is_finite() && cy.is_finite() && r.is_finite()) {
+                    return Err(CollidableError::NonFiniteCoordinate);
+                }
+                if r < 0. {
+                    return Err(CollidableError::NegativeSize);
+                }
+                Ok(Collidable::new_circle(t, i, cx, cy, r))
+            }
+            Some(BuilderShape::Rect(x, y, w, h)) => {
+                if ![x, y, w, h].iter().all(|v| v.is_finite()) {
+                    return Err(CollidableError::NonFiniteCoordinate);
+                }
+                if w < 0. || h < 0. {
+                    return Err(CollidableError::NegativeSize);
+                }
+                if w == 0. || h == 0. {
+                    return Err(CollidableError::ZeroArea);
+                }
+                Ok(Collidable::new_rect(t, i, x, y, w, h))
+            }
+            Some(BuilderShape::Polygon(points)) => Collidable::try_new_poly(
+                t,
+                i,
+                points.len(),
+                points.iter().map(|p| p.0).collect(),
+                points.iter().map(|p| p.1).collect(),
+            ),
+        }
+    }
+}
+
+/// Calls `update_normals` on every collidable with vertices; circles and ellipses are skipped.
+pub fn update_normals_batch<T>(items: &mut [Collidable<T>]) {
+    for c in items.iter_mut().filter(|c| c.nvert > 0) {
+        c.update_normals();
+    }
+}
+
+/// `update_normals_batch` with the slice split across `threads` scoped threads. Like
+/// `CollisionWorld::pairs_parallel`, this uses std threads rather than a `rayon` feature, so
+/// nalgebra stays the only required dependency.
+pub fn update_normals_batch_parallel<T: Send>(items: &mut [Collidable<T>], threads: usize) {
+    let chunk = items.len().div_ceil(threads.max(1)).max(1);
+    thread::scope(|scope| {
+        for part in items.chunks_mut(chunk) {
+            scope.spawn(move || update_normals_batch(part));
+        }
+    });
+}
+
+/// The sector a `new_arc` polygon approximates: every point within `radius` of `centre`
+/// and within `half_angle` radians of the direction at angle `direction`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Sector {
+    pub centre: (Real, Real),
+    pub radius: Real,
+    pub direction: Real,
+    pub half_angle: Real,
+}
+
+impl Sector {
+    /// Inclusive, like `Collidable::contains_point`.
+    pub fn contains_point(&self, x: Real, y: Real) -> bool {
+        let d = Vector2::new(x - self.centre.0, y - self.centre.1);
+        let len = nalgebra::norm(&d);
+        len <= self.radius && (len == 0. || self.angle_from_middle(d) <= self.half_angle)
+    }
+
+    /// Distance from the point to the sector, 0 inside it.
+    pub fn distance_to_point(&self, x: Real, y: Real) -> Real {
+        if self.contains_point(x, y) {
+            return 0.;
+        }
+        let c = Vector2::new(self.centre.0, self.centre.1);
+        let p = Vector2::new(x, y);
+        let d = p - c;
+        let mut best = Real::MAX;
+        for &a in &[
+            self.direction - self.half_angle,
+            self.direction + self.half_angle,
+        ] {
+            let end = c + Vector2::new(a.cos(), a.sin()) * self.radius;
+            let (q, _) = closest_pnts_seg_seg(c, end, p, p);
+            best = best.min(dist_sqrdf(q.x, q.y, x, y).sqrt());
+        }
+        if self.angle_from_middle(d) <= self.half_angle {
+            best = best.min(nalgebra::norm(&d) - self.radius);
+        }
+        best
+    }
+
+    fn angle_from_middle(&self, d: SupportPoint) -> Real {
+        let (cos, sin) = (self.direction.cos(), self.direction.sin());
+        (cos * d.y - sin * d.x).atan2(cos * d.x + sin * d.y).abs()
+    }
+}
+
+/// Pushes the points of the arc about `centre` that starts at offset `start` and sweeps
+/// `sweep` radians counter-clockwise, spaced `step` apart. The last point lands exactly on
+/// the end of the arc.
+fn push_arc(
+    vertx: &mut Vec<Real>,
+    verty: &mut Vec<Real>,
+    centre: Vector2<Real>,
+    start: Vector2<Real>,
+    sweep: Real,
+    step: Real,
+) {
+    let mut dir = start;
+    for _ in 0..(sweep / step).floor() as usize {
+        let p = centre + dir;
+        vertx.push(p.x);
+        verty.push(p.y);
+        dir = Rotation2::new(Vector1::new(step)).rotate(&dir);
+    }
+    let p = centre + Rotation2::new(Vector1::new(sweep)).rotate(&start);
+    vertx.push(p.x);
+    verty.push(p.y);
 }
 
 impl Collidable {
-    pub fn new_circle(t: u8, i: usize, cx: f32, cy: f32, r: f32) -> Collidable {
+    pub fn new_circle(t: u8, i: usize, cx: Real, cy: Real, r: Real) -> Collidable {
         Collidable {
             collidable_type: t,
             collidable_shape: CollidableShape::Circle,
@@ -52,47 +388,69 @@ impl Collidable {
             verty: vec![],
             normx: vec![],
             normy: vec![],
+            sector: None,
+            margin: 0.,
+            angle: 0.,
+            data: (),
+            cached_aabb: (0., 0., 0., 0.),
         }
+        .with_cached_aabb()
     }
 
+    /// A circular sector: the centre vertex followed by points along the arc, `rad` radians
+    /// wide and centred on the direction `(dirx, diry)`. The exact sector is kept in `sector`
+    /// for `arc_contains_point` and `arc_collides_circle`.
+    ///
+    /// NOTE: Only `arc_contains_point` and `arc_collides_circle` use the exact sector.
+    /// `collides` and the rest see the tessellated fan, and polygons and capsules run SAT on
+    /// it. Sectors wider than half a circle are concave, so SAT treats them roughly as their
+    /// convex hull, filling in the notch at the centre. Split them with `decompose_convex`
+    /// and use a `CompoundCollidable` when that matters.
+    #[allow(clippy::too_many_arguments)]
     pub fn new_arc(
         t: u8,
         id: usize,
-        cx: f32,
-        cy: f32,
-        r: f32,
-        dirx: f32,
-        diry: f32,
-        rad: f32,
+        cx: Real,
+        cy: Real,
+        r: Real,
+        dirx: Real,
+        diry: Real,
+        rad: Real,
+    ) -> Collidable {
+        Collidable::new_arc_with_segments(t, id, cx, cy, r, dirx, diry, rad, 16)
+    }
+
+    /// Like `new_arc`, with the arc tessellated at `segments_per_circle` segments per full
+    /// turn instead of 16.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_arc_with_segments(
+        t: u8,
+        id: usize,
+        cx: Real,
+        cy: Real,
+        r: Real,
+        dirx: Real,
+        diry: Real,
+        rad: Real,
+        segments_per_circle: usize,
     ) -> Collidable {
+        assert!(segments_per_circle > 0, "an arc needs at least one segment");
         let centre = Vector2::new(cx, cy);
 
-        let minor_arc = 2. * consts::PI * 0.0625;
-        let nvert = (rad / minor_arc).floor() as usize + 2;
+        let minor_arc = 2. * consts::PI / segments_per_circle as Real;
+        let capacity = (rad / minor_arc).floor() as usize + 2;
 
-        let mut vertx = Vec::with_capacity(nvert);
-        let mut verty = Vec::with_capacity(nvert);
+        let mut vertx = Vec::with_capacity(capacity);
+        let mut verty = Vec::with_capacity(capacity);
 
         vertx.push(centre.x);
         verty.push(centre.y);
 
-        let mut dir = Rotation2::new(Vector1::new(-rad / 2.))
-            .rotate(&nalgebra::normalize(&Vector2::new(dirx, diry)))
-            * r;
-        let mut p = centre + dir;
-
-        for _ in 0..nvert - 2 {
-            vertx.push(p.x);
-            verty.push(p.y);
-            dir = Rotation2::new(Vector1::new(minor_arc)).rotate(&dir);
-            p = centre + dir;
-        }
-        dir = Rotation2::new(Vector1::new(rad / 2.))
+        let start = Rotation2::new(Vector1::new(-rad / 2.))
             .rotate(&nalgebra::normalize(&Vector2::new(dirx, diry)))
             * r;
-        p = centre + dir;
-        vertx.push(p.x);
-        verty.push(p.y);
+        push_arc(&mut vertx, &mut verty, centre, start, rad, minor_arc);
+        let nvert = vertx.len();
 
         let normx = calc_normx(nvert, &verty);
         let normy = calc_normy(nvert, &vertx);
@@ -113,21 +471,142 @@ impl Collidable {
             verty,
             normx,
             normy,
+            sector: Some(Sector {
+                centre: (cx, cy),
+                radius: r,
+                direction: diry.atan2(dirx),
+                half_angle: rad / 2.,
+            }),
+            margin: 0.,
+            angle: 0.,
+            data: (),
+            cached_aabb: (0., 0., 0., 0.),
         }
+        .with_cached_aabb()
     }
 
     /// NOTE: Extremely close verts may cause width, height, and centres to be incorrect.
-    pub fn new_poly(t: u8, i: usize, nvert: usize, vertx: Vec<f32>, verty: Vec<f32>) -> Collidable {
-        let mut sortedx = vertx.clone();
-        sortedx.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(Equal));
-        let minx = sortedx.first().unwrap_or(&0.).clone();
-        let max = sortedx.last().unwrap_or(&0.).clone();
-        let width = max - minx;
-        let mut sortedy = verty.clone();
-        sortedy.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(Equal));
-        let miny = sortedy.first().unwrap_or(&0.).clone();
-        let max = sortedy.last().unwrap_or(&0.).clone();
-        let height = max - miny;
+    /// Panics if the vertices are rejected by `try_new_poly`.
+    pub fn new_poly(
+        t: u8,
+        i: usize,
+        nvert: usize,
+        vertx: Vec<Real>,
+        verty: Vec<Real>,
+    ) -> Collidable {
+        Collidable::try_new_poly(t, i, nvert, vertx, verty)
+            .unwrap_or_else(|e| panic!("new_poly: {}", e))
+    }
+
+    /// Rejects fewer than 3 vertices, lengths that don't match `nvert`, non-finite
+    /// coordinates and polygons with no area (e.g. all points collinear).
+    pub fn try_new_poly(
+        t: u8,
+        i: usize,
+        nvert: usize,
+        vertx: Vec<Real>,
+        verty: Vec<Real>,
+    ) -> Result<Collidable, CollidableError> {
+        if vertx.len() != nvert || verty.len() != nvert {
+            return Err(CollidableError::MismatchedLengths);
+        }
+        if nvert < 3 {
+            return Err(CollidableError::TooFewVertices);
+        }
+        if vertx.iter().chain(verty.iter()).any(|v| !v.is_finite()) {
+            return Err(CollidableError::NonFiniteCoordinate);
+        }
+        let poly = Collidable::build_poly(t, i, nvert, vertx, verty);
+        let area = poly_signed_area(nvert, &poly.vertx, &poly.verty);
+        if area.abs() <= Real::EPSILON * poly.width * poly.height {
+            return Err(CollidableError::ZeroArea);
+        }
+        Ok(poly)
+    }
+
+    /// `new_poly` from a list of points, with `nvert` taken from its length.
+    pub fn new_poly_from_points(t: u8, i: usize, points: &[(Real, Real)]) -> Collidable {
+        Collidable::new_poly(
+            t,
+            i,
+            points.len(),
+            points.iter().map(|p| p.0).collect(),
+            points.iter().map(|p| p.1).collect(),
+        )
+    }
+
+    /// `new_poly` from a list of vectors, with `nvert` taken from its length.
+    pub fn new_poly_from_vectors(t: u8, i: usize, points: &[Vector2<Real>]) -> Collidable {
+        Collidable::new_poly(
+            t,
+            i,
+            points.len(),
+            points.iter().map(|p| p.x).collect(),
+            points.iter().map(|p| p.y).collect(),
+        )
+    }
+
+    /// An empty polygon with room for `nvert_hint` vertices, to be filled with `push_vertex`
+    /// and completed with `finish`.
+    pub fn with_capacity(t: u8, i: usize, nvert_hint: usize) -> Collidable {
+        Collidable {
+            collidable_type: t,
+            collidable_shape: CollidableShape::Polygon,
+            collidable_id: i,
+
+            centrex: 0.,
+            centrey: 0.,
+            radius: 0.,
+            width: 0.,
+            height: 0.,
+
+            nvert: 0,
+            vertx: Vec::with_capacity(nvert_hint),
+            verty: Vec::with_capacity(nvert_hint),
+            normx: Vec::with_capacity(nvert_hint),
+            normy: Vec::with_capacity(nvert_hint),
+            sector: None,
+            margin: 0.,
+            angle: 0.,
+            data: (),
+            cached_aabb: (0., 0., 0., 0.),
+        }
+    }
+
+    /// Appends a vertex without touching the normals or bounds; call `finish` once done.
+    pub fn push_vertex(&mut self, x: Real, y: Real) {
+        self.vertx.push(x);
+        self.verty.push(y);
+        self.nvert += 1;
+    }
+
+    /// Computes the normals and bounds after a run of `push_vertex` calls, giving the same
+    /// polygon `new_poly` would. Nothing is validated.
+    pub fn finish(&mut self) {
+        // Filled in place to keep the capacity reserved by `with_capacity`.
+        self.normx.clear();
+        self.normy.clear();
+        for i in 0..self.nvert {
+            let j = if i + 1 < self.nvert { i + 1 } else { 0 };
+            self.normx.push(self.verty[j] - self.verty[i]);
+            self.normy.push(self.vertx[i] - self.vertx[j]);
+        }
+        self.update_bounds();
+    }
+
+    /// Builds a polygon without validating it.
+    fn build_poly(t: u8, i: usize, nvert: usize, vertx: Vec<Real>, verty: Vec<Real>) -> Collidable {
+        debug_assert!(
+            vertx.iter().chain(verty.iter()).all(|v| v.is_finite()),
+            "polygon vertices must be finite"
+        );
+        let (minx, miny, maxx, maxy) = if nvert > 0 {
+            poly_extents(nvert, &vertx, &verty)
+        } else {
+            (0., 0., 0., 0.)
+        };
+        let width = maxx - minx;
+        let height = maxy - miny;
 
         let normx = calc_normx(nvert, &verty);
         let normy = calc_normy(nvert, &vertx);
@@ -147,10 +626,35 @@ impl Collidable {
             verty,
             normx,
             normy,
+            sector: None,
+            margin: 0.,
+            angle: 0.,
+            data: (),
+            cached_aabb: (0., 0., 0., 0.),
         }
+        .with_cached_aabb()
     }
 
-    pub fn new_rect(t: u8, i: usize, x: f32, y: f32, w: f32, h: f32) -> Collidable {
+    /// A counter-clockwise polygon from the convex hull of the points. Repeated points are
+    /// ignored; a single distinct point gives a circle of radius 0 and collinear points give
+    /// a segment between the two extremes. Panics if `points` is empty.
+    pub fn from_points(t: u8, i: usize, points: &[(Real, Real)]) -> Collidable {
+        let hull = convex_hull(points);
+        match hull.len() {
+            0 => panic!("from_points needs at least one point"),
+            1 => Collidable::new_circle(t, i, hull[0].x, hull[0].y, 0.),
+            2 => Collidable::new_segment(t, i, hull[0].x, hull[0].y, hull[1].x, hull[1].y),
+            n => Collidable::build_poly(
+                t,
+                i,
+                n,
+                hull.iter().map(|p| p.x).collect(),
+                hull.iter().map(|p| p.y).collect(),
+            ),
+        }
+    }
+
+    pub fn new_rect(t: u8, i: usize, x: Real, y: Real, w: Real, h: Real) -> Collidable {
         let nvert: usize = 4;
         let mut vertx = Vec::with_capacity(nvert);
         vertx.push(x);
@@ -180,11 +684,4418 @@ impl Collidable {
             verty,
             normx,
             normy,
+            sector: None,
+            margin: 0.,
+            angle: 0.,
+            data: (),
+            cached_aabb: (0., 0., 0., 0.),
         }
+        .with_cached_aabb()
     }
 
-    pub fn update_normals(&mut self) {
-        self.normx = calc_normx(self.nvert, &self.verty);
-        self.normy = calc_normy(self.nvert, &self.vertx);
+    /// A rectangle between two corners, e.g. from another shape's `aabb()`. Corners given
+    /// the wrong way round are swapped.
+    pub fn from_aabb(
+        t: u8,
+        i: usize,
+        min_x: Real,
+        min_y: Real,
+        max_x: Real,
+        max_y: Real,
+    ) -> Collidable {
+        let (x0, x1) = (min_x.min(max_x), min_x.max(max_x));
+        let (y0, y1) = (min_y.min(max_y), min_y.max(max_y));
+        Collidable::new_rect(t, i, x0, y0, x1 - x0, y1 - y0)
+    }
+
+    /// A regular polygon inscribed in the circle of `radius` about `(cx, cy)`, with the
+    /// first vertex at angle `rotation`. The centre is kept at `(cx, cy)` even when the
+    /// bounding box is off-centre, as it is for odd `sides`.
+    pub fn new_regular(
+        t: u8,
+        i: usize,
+        cx: Real,
+        cy: Real,
+        radius: Real,
+        sides: usize,
+        rotation: Real,
+    ) -> Collidable {
+        assert!(
+            sides >= 3,
+            "a regular polygon needs at least 3 sides, got {}",
+            sides
+        );
+        let mut vertx = Vec::with_capacity(sides);
+        let mut verty = Vec::with_capacity(sides);
+        for k in 0..sides {
+            let a = rotation + 2. * consts::PI * k as Real / sides as Real;
+            vertx.push(cx + radius * a.cos());
+            verty.push(cy + radius * a.sin());
+        }
+        let mut poly = Collidable::new_poly(t, i, sides, vertx, verty);
+        poly.centrex = cx;
+        poly.centrey = cy;
+        poly
+    }
+
+    /// The points are reordered counter-clockwise if needed so the normals face outward.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_triangle(
+        t: u8,
+        i: usize,
+        x1: Real,
+        y1: Real,
+        x2: Real,
+        y2: Real,
+        x3: Real,
+        y3: Real,
+    ) -> Collidable {
+        let cross = (x2 - x1) * (y3 - y1) - (y2 - y1) * (x3 - x1);
+        if cross < 0. {
+            Collidable::new_poly(t, i, 3, vec![x1, x3, x2], vec![y1, y3, y2])
+        } else {
+            Collidable::new_poly(t, i, 3, vec![x1, x2, x3], vec![y1, y2, y3])
+        }
+    }
+
+    /// A rectangle with each corner replaced by a tessellated quarter circle. `corner_radius`
+    /// is clamped to at most half the shorter side.
+    pub fn new_rounded_rect(
+        t: u8,
+        i: usize,
+        x: Real,
+        y: Real,
+        w: Real,
+        h: Real,
+        corner_radius: Real,
+    ) -> Collidable {
+        let r = corner_radius.min(w.min(h) * 0.5);
+        if r <= 0. {
+            return Collidable::new_rect(t, i, x, y, w, h);
+        }
+        let minor_arc = 2. * consts::PI * 0.0625;
+        let corners = [
+            (x + w - r, y + r, 0., -r),
+            (x + w - r, y + h - r, r, 0.),
+            (x + r, y + h - r, 0., r),
+            (x + r, y + r, -r, 0.),
+        ];
+        let mut vertx = vec![];
+        let mut verty = vec![];
+        for (k, &(cx, cy, sx, sy)) in corners.iter().enumerate() {
+            let first = vertx.len();
+            push_arc(
+                &mut vertx,
+                &mut verty,
+                Vector2::new(cx, cy),
+                Vector2::new(sx, sy),
+                consts::PI * 0.5,
+                minor_arc,
+            );
+            // When the straight edge leading into this corner has no length, its first point
+            // duplicates the previous corner's last.
+            let edge = if k % 2 == 0 { w - 2. * r } else { h - 2. * r };
+            if edge <= 0. {
+                vertx.remove(first);
+                verty.remove(first);
+            }
+        }
+        let nvert = vertx.len();
+        Collidable::new_poly(t, i, nvert, vertx, verty)
+    }
+
+    /// A stadium: every point within `r` of the segment from `(x1, y1)` to `(x2, y2)`.
+    pub fn new_capsule(
+        t: u8,
+        i: usize,
+        x1: Real,
+        y1: Real,
+        x2: Real,
+        y2: Real,
+        r: Real,
+    ) -> Collidable {
+        let nvert = 2;
+        let vertx = vec![x1, x2];
+        let verty = vec![y1, y2];
+        let normx = calc_normx(nvert, &verty);
+        let normy = calc_normy(nvert, &vertx);
+        Collidable {
+            collidable_type: t,
+            collidable_shape: CollidableShape::Capsule,
+            collidable_id: i,
+
+            centrex: (x1 + x2) * 0.5,
+            centrey: (y1 + y2) * 0.5,
+            radius: r,
+            width: (x2 - x1).abs() + r * 2.,
+            height: (y2 - y1).abs() + r * 2.,
+
+            nvert,
+            vertx,
+            verty,
+            normx,
+            normy,
+            sector: None,
+            margin: 0.,
+            angle: 0.,
+            data: (),
+            cached_aabb: (0., 0., 0., 0.),
+        }
+        .with_cached_aabb()
+    }
+
+    /// A zero-width line from `(x1, y1)` to `(x2, y2)`, for walls and rays.
+    pub fn new_segment(t: u8, i: usize, x1: Real, y1: Real, x2: Real, y2: Real) -> Collidable {
+        let mut segment = Collidable::new_capsule(t, i, x1, y1, x2, y2, 0.);
+        segment.collidable_shape = CollidableShape::Segment;
+        segment
+    }
+
+    /// An axis-aligned ellipse with semi-axes `rx` and `ry`. Ellipses stay axis-aligned, so
+    /// rotating one only moves its centre.
+    pub fn new_ellipse(t: u8, i: usize, cx: Real, cy: Real, rx: Real, ry: Real) -> Collidable {
+        Collidable {
+            collidable_type: t,
+            collidable_shape: CollidableShape::Ellipse,
+            collidable_id: i,
+
+            centrex: cx,
+            centrey: cy,
+            radius: 0.,
+            width: rx * 2.,
+            height: ry * 2.,
+
+            nvert: 0,
+            vertx: vec![],
+            verty: vec![],
+            normx: vec![],
+            normy: vec![],
+            sector: None,
+            margin: 0.,
+            angle: 0.,
+            data: (),
+            cached_aabb: (0., 0., 0., 0.),
+        }
+        .with_cached_aabb()
+    }
+
+    /// Sweeps a circle of radius `r` from `(cx, cy)` by `(vx, vy)` and returns the fraction of
+    /// the motion, in `[0, 1]`, at which it first touches `other`. A circle that already
+    /// overlaps `other` returns 0. Unlike `collides`, this can't miss a thin wall crossed
+    /// within one step.
+    ///
+    /// NOTE: This is called as `Collidable::sweep_circle(...)` rather than on a shape. The
+    /// moving circle is fully given by `(cx, cy)` and `r`, so a `self` would go unused.
+    pub fn sweep_circle<U>(
+        cx: Real,
+        cy: Real,
+        r: Real,
+        vx: Real,
+        vy: Real,
+        other: &Collidable<U>,
+    ) -> Option<Real> {
+        if Collidable::new_circle(0, 0, cx, cy, r).collides(other) {
+            return Some(0.);
+        }
+        let len = (vx * vx + vy * vy).sqrt();
+        if len == 0. {
+            return None;
+        }
+        let o = Vector2::new(cx, cy);
+        let d = Vector2::new(vx / len, vy / len);
+        let t = match other.collidable_shape {
+            CollidableShape::Ellipse => other.ellipse_polygon().raycast_rounded(o, d, r),
+            _ => other.raycast_rounded(o, d, r + other.rounding()),
+        }?;
+        if t <= len {
+            Some(t / len)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Collidable<T> {
+    pub fn area(&self) -> Real {
+        self.signed_area().abs()
+    }
+
+    /// Polygon area is negative for clockwise winding. The other shapes are always positive,
+    /// except segments which have no area.
+    pub fn signed_area(&self) -> Real {
+        match self.collidable_shape {
+            CollidableShape::Polygon => poly_signed_area(self.nvert, &self.vertx, &self.verty),
+            CollidableShape::Circle => consts::PI * sqrf(self.radius),
+            CollidableShape::Ellipse => consts::PI * self.width * 0.5 * self.height * 0.5,
+            CollidableShape::Capsule => {
+                let length =
+                    dist_sqrdf(self.vertx[0], self.verty[0], self.vertx[1], self.verty[1]).sqrt();
+                consts::PI * sqrf(self.radius) + 2. * self.radius * length
+            }
+            CollidableShape::Segment => 0.,
+        }
+    }
+
+    /// Rotational inertia of a uniform body of `mass` with this shape, about its `centroid`.
+    /// That's not `(centrex, centrey)` for most polygons, see `set_centre_to_centroid`.
+    /// Segments are treated as thin rods.
+    pub fn moment_of_inertia(&self, mass: Real) -> Real {
+        match self.collidable_shape {
+            CollidableShape::Polygon => mass * poly_inertia(self.nvert, &self.vertx, &self.verty),
+            CollidableShape::Circle => 0.5 * mass * sqrf(self.radius),
+            CollidableShape::Ellipse => mass * (sqrf(self.width) + sqrf(self.height)) / 16.,
+            CollidableShape::Capsule => {
+                let (r, len) = (self.radius, self.edge_length(0));
+                let area = self.area();
+                if area == 0. {
+                    return 0.;
+                }
+                let disc_mass = mass * consts::PI * sqrf(r) / area;
+                let box_mass = mass - disc_mass;
+                // The end caps are half discs pushed out to either end of the spine.
+                let caps =
+                    disc_mass * (sqrf(r) / 2. + sqrf(len) / 4. + 4. * len * r / (3. * consts::PI));
+                box_mass * (sqrf(len) + sqrf(2. * r)) / 12. + caps
+            }
+            CollidableShape::Segment => mass * sqrf(self.edge_length(0)) / 12.,
+        }
+    }
+
+    /// Ellipses use Ramanujan's approximation. A segment's perimeter is its length.
+    pub fn perimeter(&self) -> Real {
+        match self.collidable_shape {
+            CollidableShape::Circle => 2. * consts::PI * self.radius,
+            CollidableShape::Ellipse => {
+                let (a, b) = (self.width * 0.5, self.height * 0.5);
+                consts::PI * (3. * (a + b) - ((3. * a + b) * (a + 3. * b)).sqrt())
+            }
+            CollidableShape::Polygon => (0..self.nvert).map(|i| self.edge_length(i)).sum(),
+            CollidableShape::Capsule => 2. * consts::PI * self.radius + 2. * self.edge_length(0),
+            CollidableShape::Segment => self.edge_length(0),
+        }
+    }
+
+    /// Like `perimeter`, but for shapes built by `new_arc` it only measures the curved edge,
+    /// skipping the two edges back to the centre vertex.
+    pub fn boundary_length(&self) -> Real {
+        let sector = match self.sector {
+            Some(sector) if self.nvert > 2 => sector,
+            _ => return self.perimeter(),
+        };
+        // The centre vertex starts the fan, but mirroring reverses the order, so find it.
+        let (cx, cy) = sector.centre;
+        let centre = (0..self.nvert)
+            .min_by(|&a, &b| {
+                let da = dist_sqrdf(self.vertx[a], self.verty[a], cx, cy);
+                let db = dist_sqrdf(self.vertx[b], self.verty[b], cx, cy);
+                da.partial_cmp(&db).unwrap_or(cmp::Ordering::Equal)
+            })
+            .unwrap_or(0);
+        let before = (centre + self.nvert - 1) % self.nvert;
+        (0..self.nvert)
+            .filter(|&i| i != centre && i != before)
+            .map(|i| self.edge_length(i))
+            .sum()
+    }
+
+    fn edge_length(&self, i: usize) -> Real {
+        let (a, b) = self.core_edge(i);
+        dist_sqrdf(a.x, a.y, b.x, b.y).sqrt()
+    }
+
+    /// The centre of mass. For polygons this differs from `centrex`/`centrey`, which
+    /// `new_poly` sets to the middle of the bounding box; the two only agree for symmetric
+    /// shapes.
+    pub fn centroid(&self) -> (Real, Real) {
+        match self.collidable_shape {
+            CollidableShape::Polygon => poly_centroid(self.nvert, &self.vertx, &self.verty),
+            CollidableShape::Circle | CollidableShape::Ellipse => (self.centrex, self.centrey),
+            CollidableShape::Capsule | CollidableShape::Segment => (
+                (self.vertx[0] + self.vertx[1]) * 0.5,
+                (self.verty[0] + self.verty[1]) * 0.5,
+            ),
+        }
+    }
+
+    /// Moves `centrex`/`centrey` to the centroid, so `rotate` and `scale` work about the
+    /// centre of mass. The vertices don't move.
+    pub fn set_centre_to_centroid(&mut self) {
+        let (x, y) = self.centroid();
+        self.centrex = x;
+        self.centrey = y;
+    }
+
+    /// Vertex order of a polygon, from the sign of its area. Zero-area polygons and the other
+    /// shapes report `CounterClockwise`.
+    pub fn winding(&self) -> Winding {
+        match self.collidable_shape {
+            CollidableShape::Polygon
+                if poly_signed_area(self.nvert, &self.vertx, &self.verty) < 0. =>
+            {
+                Winding::Clockwise
+            }
+            _ => Winding::CounterClockwise,
+        }
+    }
+
+    /// Reverses a clockwise polygon so that its normals point outward.
+    pub fn ensure_ccw(&mut self) {
+        if self.winding() == Winding::Clockwise {
+            self.vertx.reverse();
+            self.verty.reverse();
+            self.update_normals();
+        }
+    }
+
+    /// Whether a polygon's edges only meet at shared vertices. Area, centroid and the
+    /// collision tests assume this. A repeated vertex makes its neighbouring edges touch, so
+    /// run `simplify_collinear` on imported outlines first. Other shapes are always simple.
+    pub fn is_simple(&self) -> bool {
+        match self.collidable_shape {
+            CollidableShape::Polygon => poly_is_simple(self.nvert, &self.vertx, &self.verty),
+            _ => true,
+        }
+    }
+
+    /// Checks the invariants the collision code relies on: `vertx`, `verty`, `normx` and
+    /// `normy` all hold `nvert` entries, every coordinate and size is finite and sizes aren't
+    /// negative. Polygons must also have at least 3 vertices, not cross themselves, and have
+    /// normals matching their current vertices, i.e. `update_normals` was called after the
+    /// last edit.
+    ///
+    /// NOTE: Constructors don't call this, even in debug builds. `new_poly` panics on the
+    /// layouts `try_new_poly` rejects, but self-intersecting polygons and negative sizes can
+    /// still be built for `is_simple` and this to report.
+    pub fn validate(&self) -> Result<(), CollidableError> {
+        self.validate_layout()?;
+        if let CollidableShape::Polygon = self.collidable_shape {
+            if self.nvert < 3 {
+                return Err(CollidableError::TooFewVertices);
+            }
+            if !self.is_simple() {
+                return Err(CollidableError::SelfIntersecting);
+            }
+            let normx = calc_normx(self.nvert, &self.verty);
+            let normy = calc_normy(self.nvert, &self.vertx);
+            let scale = self
+                .vertx
+                .iter()
+                .chain(&self.verty)
+                .fold(1., |m: Real, v| m.max(v.abs()));
+            // Translating rounds each vertex but leaves the normals alone, so allow for that.
+            let tolerance = 16. * Real::EPSILON * scale;
+            for i in 0..self.nvert {
+                if (self.normx[i] - normx[i]).abs() > tolerance
+                    || (self.normy[i] - normy[i]).abs() > tolerance
+                {
+                    return Err(CollidableError::StaleNormals);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The part of `validate` every shape must pass, whatever its kind.
+    fn validate_layout(&self) -> Result<(), CollidableError> {
+        if self.vertx.len() != self.nvert || self.verty.len() != self.nvert {
+            return Err(CollidableError::MismatchedLengths);
+        }
+        if self.normx.len() != self.nvert || self.normy.len() != self.nvert {
+            return Err(CollidableError::MismatchedNormals);
+        }
+        let sizes = [self.radius, self.width, self.height, self.margin];
+        let finite = [self.centrex, self.centrey, self.angle]
+            .iter()
+            .chain(&sizes)
+            .chain(&self.vertx)
+            .chain(&self.verty)
+            .all(|v| v.is_finite());
+        if !finite {
+            return Err(CollidableError::NonFiniteCoordinate);
+        }
+        if sizes.iter().any(|&v| v < 0.) {
+            return Err(CollidableError::NegativeSize);
+        }
+        Ok(())
+    }
+
+    /// Whether SAT can be trusted for this shape. Only polygons can be concave; see
+    /// `decompose_convex` for those that are.
+    pub fn is_convex(&self) -> bool {
+        match self.collidable_shape {
+            CollidableShape::Polygon => poly_is_convex(self.nvert, &self.vertx, &self.verty),
+            _ => true,
+        }
+    }
+
+    /// Splits a simple, possibly concave polygon into convex polygons with the same type, id,
+    /// margin and angle, ready for a `CompoundCollidable`. Convex polygons and other shapes
+    /// come back as a single clone; otherwise collinear vertices are dropped from the pieces.
+    pub fn decompose_convex(&self) -> Vec<Collidable<T>>
+    where
+        T: Clone,
+    {
+        if let CollidableShape::Polygon = self.collidable_shape {
+            let pieces = decompose_convex_poly(self.nvert, &self.vertx, &self.verty);
+            if pieces.len() > 1 {
+                return pieces
+                    .into_iter()
+                    .map(|(vertx, verty)| {
+                        let mut piece = Collidable::new_poly(
+                            self.collidable_type,
+                            self.collidable_id,
+                            vertx.len(),
+                            vertx,
+                            verty,
+                        );
+                        piece.margin = self.margin;
+                        piece.angle = self.angle;
+                        piece.with_data(self.data.clone())
+                    })
+                    .collect();
+            }
+        }
+        vec![self.clone()]
+    }
+
+    /// A polygon approximating an ellipse, used for every ellipse test except against points
+    /// and circles.
+    fn ellipse_polygon(&self) -> Collidable {
+        let (rx, ry) = (self.width * 0.5, self.height * 0.5);
+        let mut vertx = Vec::with_capacity(ELLIPSE_SEGMENTS);
+        let mut verty = Vec::with_capacity(ELLIPSE_SEGMENTS);
+        for k in 0..ELLIPSE_SEGMENTS {
+            let a = 2. * consts::PI * k as Real / ELLIPSE_SEGMENTS as Real;
+            vertx.push(self.centrex + rx * a.cos());
+            verty.push(self.centrey + ry * a.sin());
+        }
+        Collidable::build_poly(
+            self.collidable_type,
+            self.collidable_id,
+            ELLIPSE_SEGMENTS,
+            vertx,
+            verty,
+        )
+    }
+
+    /// The same shape carrying `data` in place of its current payload.
+    pub fn with_data<U>(self, data: U) -> Collidable<U> {
+        Collidable {
+            collidable_type: self.collidable_type,
+            collidable_shape: self.collidable_shape,
+            collidable_id: self.collidable_id,
+
+            centrex: self.centrex,
+            centrey: self.centrey,
+            radius: self.radius,
+            width: self.width,
+            height: self.height,
+
+            nvert: self.nvert,
+            vertx: self.vertx,
+            verty: self.verty,
+            normx: self.normx,
+            normy: self.normy,
+            sector: self.sector,
+            margin: self.margin,
+            angle: self.angle,
+            data,
+            cached_aabb: self.cached_aabb,
+        }
+    }
+
+    /// A copy of the geometry alone, for scratch work that doesn't need `data`.
+    fn without_data(&self) -> Collidable {
+        Collidable {
+            collidable_type: self.collidable_type,
+            collidable_shape: self.collidable_shape,
+            collidable_id: self.collidable_id,
+
+            centrex: self.centrex,
+            centrey: self.centrey,
+            radius: self.radius,
+            width: self.width,
+            height: self.height,
+
+            nvert: self.nvert,
+            vertx: self.vertx.clone(),
+            verty: self.verty.clone(),
+            normx: self.normx.clone(),
+            normy: self.normy.clone(),
+            sector: self.sector,
+            margin: self.margin,
+            angle: self.angle,
+            data: (),
+            cached_aabb: self.cached_aabb,
+        }
+    }
+
+    /// A copy with a new `collidable_id`, for stamping out instances of a prototype.
+    pub fn clone_with_id(&self, new_id: usize) -> Collidable<T>
+    where
+        T: Clone,
+    {
+        self.clone_with(new_id, self.collidable_type)
+    }
+
+    /// A copy with a new `collidable_id` and `collidable_type`.
+    pub fn clone_with(&self, new_id: usize, new_type: u8) -> Collidable<T>
+    where
+        T: Clone,
+    {
+        Collidable {
+            collidable_id: new_id,
+            collidable_type: new_type,
+            ..self.clone()
+        }
+    }
+
+    pub fn translate(&mut self, dx: Real, dy: Real) {
+        self.centrex += dx;
+        self.centrey += dy;
+        for i in 0..self.nvert {
+            self.vertx[i] += dx;
+            self.verty[i] += dy;
+        }
+        if let Some(ref mut sector) = self.sector {
+            sector.centre = (sector.centre.0 + dx, sector.centre.1 + dy);
+        }
+        self.refresh_aabb();
+        // Normals only depend on edge directions, so they're unchanged by translation.
+    }
+
+    /// Moves the shape so `(centrex, centrey)` lands on `(x, y)`. For `new_poly` shapes that
+    /// centre is the middle of the bounding box, not the centroid, unless it was moved with
+    /// `set_centre_to_centroid`.
+    pub fn set_position(&mut self, x: Real, y: Real) {
+        let (dx, dy) = (x - self.centrex, y - self.centrey);
+        self.translate(dx, dy);
+        self.centrex = x;
+        self.centrey = y;
+        self.refresh_aabb();
+    }
+
+    /// Rotates counter-clockwise about `(centrex, centrey)`. For `new_poly` shapes that's the
+    /// bounding-box centre rather than the centroid; use `rotate_around` for another pivot.
+    pub fn rotate(&mut self, radians: Real) {
+        let (x, y) = (self.centrex, self.centrey);
+        self.rotate_around(x, y, radians);
+    }
+
+    /// Rotates about `(centrex, centrey)` so `angle` becomes `radians`.
+    pub fn set_angle(&mut self, radians: Real) {
+        let delta = radians - self.angle;
+        self.rotate(delta);
+        self.angle = radians;
+    }
+
+    /// Rotates counter-clockwise about `(x, y)`. Circles only have their centre moved, but
+    /// their `angle` still turns.
+    pub fn rotate_around(&mut self, x: Real, y: Real, radians: Real) {
+        self.angle += radians;
+        let rot = Rotation2::new(Vector1::new(radians));
+        let pivot = Vector2::new(x, y);
+        let c = pivot + rot.rotate(&(Vector2::new(self.centrex, self.centrey) - pivot));
+        self.centrex = c.x;
+        self.centrey = c.y;
+        if let Some(ref mut sector) = self.sector {
+            let sc = Vector2::new(sector.centre.0, sector.centre.1);
+            let sc = pivot + rot.rotate(&(sc - pivot));
+            sector.centre = (sc.x, sc.y);
+            sector.direction += radians;
+        }
+        if self.nvert > 0 {
+            for i in 0..self.nvert {
+                let v = pivot + rot.rotate(&(Vector2::new(self.vertx[i], self.verty[i]) - pivot));
+                self.vertx[i] = v.x;
+                self.verty[i] = v.y;
+            }
+            self.update_size();
+            self.update_normals();
+        } else {
+            self.refresh_aabb();
+        }
+    }
+
+    /// Scales about `(centrex, centrey)`.
+    ///
+    /// NOTE: Circles can't become ellipses, so a non-uniform scale grows the radius by the
+    /// larger of the two factors, giving a circle that encloses the true ellipse. Capsules
+    /// scale their spine exactly and their radius the same way.
+    pub fn scale(&mut self, sx: Real, sy: Real) {
+        // A sector only stays one under a uniform scale, possibly mirrored.
+        self.sector = match self.sector {
+            Some(sector) if sx.abs() == sy.abs() => Some(Sector {
+                centre: (
+                    self.centrex + (sector.centre.0 - self.centrex) * sx,
+                    self.centrey + (sector.centre.1 - self.centrey) * sy,
+                ),
+                radius: sector.radius * sx.abs(),
+                direction: (sector.direction.sin() * sy).atan2(sector.direction.cos() * sx),
+                half_angle: sector.half_angle,
+            }),
+            _ => None,
+        };
+        match self.collidable_shape {
+            CollidableShape::Circle => {
+                self.radius *= sx.abs().max(sy.abs());
+                self.width = self.radius * 2.;
+                self.height = self.radius * 2.;
+            }
+            CollidableShape::Polygon => {
+                for i in 0..self.nvert {
+                    self.vertx[i] = self.centrex + (self.vertx[i] - self.centrex) * sx;
+                    self.verty[i] = self.centrey + (self.verty[i] - self.centrey) * sy;
+                }
+                self.width *= sx.abs();
+                self.height *= sy.abs();
+                // A mirror flips the winding, which would turn the normals inward.
+                if sx * sy < 0. {
+                    self.vertx.reverse();
+                    self.verty.reverse();
+                }
+                self.update_normals();
+            }
+            CollidableShape::Ellipse => {
+                self.width *= sx.abs();
+                self.height *= sy.abs();
+            }
+            CollidableShape::Capsule | CollidableShape::Segment => {
+                for i in 0..self.nvert {
+                    self.vertx[i] = self.centrex + (self.vertx[i] - self.centrex) * sx;
+                    self.verty[i] = self.centrey + (self.verty[i] - self.centrey) * sy;
+                }
+                self.radius *= sx.abs().max(sy.abs());
+                self.width = (self.vertx[1] - self.vertx[0]).abs() + self.radius * 2.;
+                self.height = (self.verty[1] - self.verty[0]).abs() + self.radius * 2.;
+                self.update_normals();
+            }
+        }
+        self.refresh_aabb();
+    }
+
+    /// Mirrors across the vertical line `x = axis_x`. Polygon vertices are reversed to keep
+    /// their winding, so the normals still point outward.
+    pub fn flip_x(&mut self, axis_x: Real) {
+        self.centrex = 2. * axis_x - self.centrex;
+        for x in &mut self.vertx {
+            *x = 2. * axis_x - *x;
+        }
+        if let Some(ref mut sector) = self.sector {
+            sector.centre.0 = 2. * axis_x - sector.centre.0;
+            sector.direction = consts::PI - sector.direction;
+        }
+        self.after_mirror();
+    }
+
+    /// Mirrors across the horizontal line `y = axis_y`, like `flip_x`.
+    pub fn flip_y(&mut self, axis_y: Real) {
+        self.centrey = 2. * axis_y - self.centrey;
+        for y in &mut self.verty {
+            *y = 2. * axis_y - *y;
+        }
+        if let Some(ref mut sector) = self.sector {
+            sector.centre.1 = 2. * axis_y - sector.centre.1;
+            sector.direction = -sector.direction;
+        }
+        self.after_mirror();
+    }
+
+    fn after_mirror(&mut self) {
+        self.refresh_aabb();
+        if self.nvert == 0 {
+            return;
+        }
+        if let CollidableShape::Polygon = self.collidable_shape {
+            self.vertx.reverse();
+            self.verty.reverse();
+        }
+        self.update_normals();
+    }
+
+    pub fn update_normals(&mut self) {
+        self.normx = calc_normx(self.nvert, &self.verty);
+        self.normy = calc_normy(self.nvert, &self.vertx);
+    }
+
+    /// Recomputes `width`, `height` and the centre from the vertices after editing them
+    /// directly, and refreshes the cached `aabb`. The centre is the middle of the bounding
+    /// box, as in `new_poly`. Circles and ellipses only have their `aabb` refreshed.
+    pub fn update_bounds(&mut self) {
+        self.refresh_aabb();
+        if self.nvert == 0 {
+            return;
+        }
+        let (minx, miny, maxx, maxy) = self.cached_aabb;
+        self.width = maxx - minx;
+        self.height = maxy - miny;
+        self.centrex = minx + self.width * 0.5;
+        self.centrey = miny + self.height * 0.5;
+    }
+
+    /// Releases spare capacity in the vertex and normal vectors, e.g. after `simplify_rdp`.
+    pub fn shrink_to_fit(&mut self) {
+        for v in &mut [
+            &mut self.vertx,
+            &mut self.verty,
+            &mut self.normx,
+            &mut self.normy,
+        ] {
+            v.truncate(self.nvert);
+            v.shrink_to_fit();
+        }
+    }
+
+    /// Heap bytes reserved by the vertex and normal vectors, not counting the struct itself.
+    pub fn memory_bytes(&self) -> usize {
+        (self.vertx.capacity()
+            + self.verty.capacity()
+            + self.normx.capacity()
+            + self.normy.capacity())
+            * mem::size_of::<Real>()
+    }
+
+    /// Drops polygon vertices whose turn is within `epsilon` radians of straight, including
+    /// repeated vertices, but never below 3. Updates `nvert`, the normals, `width` and
+    /// `height`; the centre is kept.
+    pub fn simplify_collinear(&mut self, epsilon: Real) {
+        match self.collidable_shape {
+            CollidableShape::Polygon => {}
+            _ => return,
+        }
+        let mut removed = false;
+        while self.nvert > 3 {
+            let n = self.nvert;
+            let straight = (0..n).find(|&i| {
+                let (prev, next) = ((i + n - 1) % n, (i + 1) % n);
+                let (ex, ey) = (
+                    self.vertx[i] - self.vertx[prev],
+                    self.verty[i] - self.verty[prev],
+                );
+                let (fx, fy) = (
+                    self.vertx[next] - self.vertx[i],
+                    self.verty[next] - self.verty[i],
+                );
+                (ex * fy - ey * fx).atan2(ex * fx + ey * fy).abs() <= epsilon
+            });
+            match straight {
+                Some(i) => {
+                    self.vertx.remove(i);
+                    self.verty.remove(i);
+                    self.nvert -= 1;
+                    removed = true;
+                }
+                None => break,
+            }
+        }
+        if removed {
+            self.sector = None;
+            self.update_normals();
+            self.update_size();
+        }
+    }
+
+    /// Inserts the midpoint of every polygon edge, `iterations` times, doubling `nvert` each
+    /// time. The outline, bounds and centre don't change. Other shapes are left alone.
+    pub fn subdivide(&mut self, iterations: usize) {
+        match self.collidable_shape {
+            CollidableShape::Polygon => {}
+            _ => return,
+        }
+        for _ in 0..iterations {
+            let n = self.nvert;
+            let mut vertx = Vec::with_capacity(n * 2);
+            let mut verty = Vec::with_capacity(n * 2);
+            for i in 0..n {
+                let j = (i + 1) % n;
+                vertx.push(self.vertx[i]);
+                verty.push(self.verty[i]);
+                vertx.push((self.vertx[i] + self.vertx[j]) * 0.5);
+                verty.push((self.verty[i] + self.verty[j]) * 0.5);
+            }
+            self.vertx = vertx;
+            self.verty = verty;
+            self.nvert = n * 2;
+        }
+        self.update_normals();
+    }
+
+    /// Ramer-Douglas-Peucker decimation of a polygon: drops vertices that stay within
+    /// `tolerance` of the simplified outline. Polygons that would fall below 3 vertices are
+    /// left alone. Updates `nvert`, the normals, `width` and `height`; the centre is kept.
+    pub fn simplify_rdp(&mut self, tolerance: Real) {
+        match self.collidable_shape {
+            CollidableShape::Polygon => {}
+            _ => return,
+        }
+        let keep = simplify_rdp_ring(self.nvert, &self.vertx, &self.verty, tolerance);
+        if keep.len() < 3 || keep.len() == self.nvert {
+            return;
+        }
+        self.vertx = keep.iter().map(|&i| self.vertx[i]).collect();
+        self.verty = keep.iter().map(|&i| self.verty[i]).collect();
+        self.nvert = keep.len();
+        self.sector = None;
+        self.update_normals();
+        self.update_size();
+    }
+
+    /// Grows the shape outward by `amount`, or shrinks it when negative. Polygon edges move
+    /// along their normals and meet at new, sharp corners; a segment grows into a capsule.
+    ///
+    /// Returns false and leaves the shape unchanged if shrinking would make it vanish or,
+    /// for polygons, make an edge shrink past zero length and turn inside out.
+    pub fn inflate(&mut self, amount: Real) -> bool {
+        match self.collidable_shape {
+            CollidableShape::Circle | CollidableShape::Capsule => {
+                if self.radius + amount < 0. {
+                    return false;
+                }
+                self.radius += amount;
+                self.width += 2. * amount;
+                self.height += 2. * amount;
+                self.refresh_aabb();
+            }
+            CollidableShape::Segment => {
+                if amount < 0. {
+                    return false;
+                }
+                self.collidable_shape = CollidableShape::Capsule;
+                self.radius = amount;
+                self.width += 2. * amount;
+                self.height += 2. * amount;
+                self.refresh_aabb();
+            }
+            CollidableShape::Ellipse => {
+                if self.width + 2. * amount < 0. || self.height + 2. * amount < 0. {
+                    return false;
+                }
+                self.width += 2. * amount;
+                self.height += 2. * amount;
+                self.refresh_aabb();
+            }
+            CollidableShape::Polygon => {
+                let n = self.nvert;
+                // Unit outward normals, whatever the winding.
+                let sign = match self.winding() {
+                    Winding::Clockwise => -1.,
+                    Winding::CounterClockwise => 1.,
+                };
+                let normals: Vec<SupportPoint> = (0..n)
+                    .map(|i| {
+                        let v = Vector2::new(self.normx[i], self.normy[i]) * sign;
+                        let len = nalgebra::norm(&v);
+                        if len == 0. {
+                            v
+                        } else {
+                            v / len
+                        }
+                    })
+                    .collect();
+                let mut vertx = Vec::with_capacity(n);
+                let mut verty = Vec::with_capacity(n);
+                for i in 0..n {
+                    let (a, b) = (normals[(i + n - 1) % n], normals[i]);
+                    let k = 1. + nalgebra::dot(&a, &b);
+                    let offset = if k > Real::EPSILON {
+                        (a + b) * (amount / k)
+                    } else {
+                        b * amount
+                    };
+                    vertx.push(self.vertx[i] + offset.x);
+                    verty.push(self.verty[i] + offset.y);
+                }
+                for i in 0..n {
+                    let j = (i + 1) % n;
+                    let old = (self.vertx[j] - self.vertx[i], self.verty[j] - self.verty[i]);
+                    let new = (vertx[j] - vertx[i], verty[j] - verty[i]);
+                    if old.0 * new.0 + old.1 * new.1 <= 0. && (old.0 != 0. || old.1 != 0.) {
+                        return false;
+                    }
+                }
+                self.vertx = vertx;
+                self.verty = verty;
+                self.sector = None;
+                self.update_normals();
+                self.update_size();
+            }
+        }
+        true
+    }
+
+    /// `width` and `height` from the current vertices, without moving the centre.
+    fn update_size(&mut self) {
+        self.refresh_aabb();
+        let (minx, miny, maxx, maxy) = self.cached_aabb;
+        self.width = maxx - minx;
+        self.height = maxy - miny;
+    }
+
+    /// Moves one vertex, refreshing the two normals touching it and the bounds as in
+    /// `update_bounds`. Panics if `index` is out of range.
+    pub fn set_vertex(&mut self, index: usize, x: Real, y: Real) {
+        assert!(
+            index < self.nvert,
+            "vertex {} out of range for {} vertices",
+            index,
+            self.nvert
+        );
+        self.vertx[index] = x;
+        self.verty[index] = y;
+        self.sector = None;
+        if self.normx.len() == self.nvert && self.normy.len() == self.nvert {
+            let prev = if index == 0 {
+                self.nvert - 1
+            } else {
+                index - 1
+            };
+            let next = if index + 1 < self.nvert { index + 1 } else { 0 };
+            for &(i, j) in &[(prev, index), (index, next)] {
+                self.normx[i] = self.verty[j] - self.verty[i];
+                self.normy[i] = self.vertx[i] - self.vertx[j];
+            }
+        } else {
+            self.update_normals();
+        }
+        self.update_bounds();
+    }
+
+    /// Whether both describe the same geometry to within `epsilon`: the shape, centre,
+    /// radius, size and vertices in order. `collidable_type`, `collidable_id` and the
+    /// derived normals are ignored.
+    pub fn approx_eq<U>(&self, other: &Collidable<U>, epsilon: Real) -> bool {
+        let close = |a: Real, b: Real| (a - b).abs() <= epsilon;
+        self.collidable_shape == other.collidable_shape
+            && self.nvert == other.nvert
+            && close(self.centrex, other.centrex)
+            && close(self.centrey, other.centrey)
+            && close(self.radius, other.radius)
+            && close(self.width, other.width)
+            && close(self.height, other.height)
+            && self
+                .vertices()
+                .zip(other.vertices())
+                .all(|(a, b)| close(a.x, b.x) && close(a.y, b.y))
+    }
+
+    /// Each edge as `(start, end)`, including the closing edge of a polygon. Capsules and
+    /// segments yield their spine; circles and ellipses yield nothing.
+    pub fn edges(&self) -> impl Iterator<Item = ((Real, Real), (Real, Real))> + '_ {
+        let count = match self.collidable_shape {
+            CollidableShape::Polygon => self.nvert,
+            CollidableShape::Capsule | CollidableShape::Segment => 1,
+            CollidableShape::Circle | CollidableShape::Ellipse => 0,
+        };
+        (0..count).map(move |i| {
+            let (a, b) = self.core_edge(i);
+            ((a.x, a.y), (b.x, b.y))
+        })
+    }
+
+    /// The `nvert` stored vertices. Empty for circles and ellipses.
+    pub fn vertices(&self) -> impl Iterator<Item = Vector2<Real>> + '_ {
+        self.vertx[..self.nvert]
+            .iter()
+            .zip(&self.verty[..self.nvert])
+            .map(|(&x, &y)| Vector2::new(x, y))
+    }
+
+    /// The stored edge normals, one per vertex, in the same order as `vertices`.
+    pub fn normals(&self) -> impl Iterator<Item = Vector2<Real>> + '_ {
+        self.normx
+            .iter()
+            .zip(&self.normy)
+            .take(self.nvert)
+            .map(|(&x, &y)| Vector2::new(x, y))
+    }
+
+    /// Polygon pairs are tested with the Separating Axis Theorem over the normals of both
+    /// shapes, circle pairs by comparing squared centre distance against the summed radii.
+    /// A circle hits a polygon when its centre is inside or within `radius` of an edge.
+    /// Capsules compare the distance from their spine against the summed radii.
+    /// Shapes that only touch along an edge or at a single point are not colliding, except
+    /// segments: having no area, any contact with a segment counts, including collinear
+    /// overlap and one segment ending on another.
+    ///
+    /// NOTE: SAT is only exact for convex polygons. A concave polygon behaves roughly like
+    /// its convex hull, so overlaps inside a concavity may be reported as collisions.
+    ///
+    /// NOTE: Ellipses are exact against circles but approximated by a tessellated polygon
+    /// against every other shape.
+    ///
+    /// Each shape is treated as grown by its `margin`, so shapes whose gap is below the
+    /// summed margins collide too.
+    pub fn collides<U>(&self, other: &Collidable<U>) -> bool {
+        let margin = self.margin + other.margin;
+        self.collides_core(other) || (margin > 0. && self.distance(other) < margin)
+    }
+
+    /// `collides` on the bare geometry, ignoring margins.
+    fn collides_core<U>(&self, other: &Collidable<U>) -> bool {
+        match (self.collidable_shape, other.collidable_shape) {
+            (CollidableShape::Ellipse, CollidableShape::Circle) => {
+                self.collides_circle_ellipse(other)
+            }
+            (CollidableShape::Circle, CollidableShape::Ellipse) => {
+                other.collides_circle_ellipse(self)
+            }
+            (CollidableShape::Ellipse, _) => self.ellipse_polygon().collides_core(other),
+            (_, CollidableShape::Ellipse) => self.collides_core(&other.ellipse_polygon()),
+            (CollidableShape::Polygon, CollidableShape::Polygon) => {
+                !self.separated_by_normals(other) && !other.separated_by_normals(self)
+            }
+            (CollidableShape::Circle, CollidableShape::Circle) => {
+                dist_sqrdf(self.centrex, self.centrey, other.centrex, other.centrey)
+                    < sqrf(self.radius + other.radius)
+            }
+            (CollidableShape::Circle, CollidableShape::Polygon) => other.collides_circle(self),
+            (CollidableShape::Polygon, CollidableShape::Circle) => self.collides_circle(other),
+            (CollidableShape::Segment, CollidableShape::Segment) => {
+                let (a1, a2) = self.core_edge(0);
+                let (b1, b2) = other.core_edge(0);
+                segments_intersect(a1, a2, b1, b2)
+            }
+            (CollidableShape::Segment, CollidableShape::Polygon) => other.collides_segment(self),
+            (CollidableShape::Polygon, CollidableShape::Segment) => self.collides_segment(other),
+            (CollidableShape::Segment, _) | (_, CollidableShape::Segment) => {
+                let (p, q) = self.closest_core_points(other);
+                dist_sqrdf(p.x, p.y, q.x, q.y) <= sqrf(self.radius + other.radius)
+            }
+            (CollidableShape::Capsule, CollidableShape::Polygon) => other.collides_capsule(self),
+            (CollidableShape::Polygon, CollidableShape::Capsule) => self.collides_capsule(other),
+            (CollidableShape::Capsule, _) | (_, CollidableShape::Capsule) => {
+                let (p, q) = self.closest_core_points(other);
+                dist_sqrdf(p.x, p.y, q.x, q.y) < sqrf(self.radius + other.radius)
+            }
+        }
+    }
+
+    /// Like `contains_point`, but shapes from `new_arc` are tested against their exact
+    /// sector rather than the tessellated polygon.
+    pub fn arc_contains_point(&self, x: Real, y: Real) -> bool {
+        match self.sector {
+            Some(ref sector) => sector.contains_point(x, y),
+            None => self.contains_point(x, y),
+        }
+    }
+
+    /// Like `collides` against a circle, but using the exact sector of a `new_arc` shape.
+    pub fn arc_collides_circle<U>(&self, circle: &Collidable<U>) -> bool {
+        match (self.sector, circle.collidable_shape) {
+            (Some(ref sector), CollidableShape::Circle) => {
+                sector.distance_to_point(circle.centrex, circle.centrey) < circle.radius
+            }
+            _ => self.collides(circle),
+        }
+    }
+
+    /// Whether `self` collides with any of `others`.
+    pub fn collides_any<U>(&self, others: &[Collidable<U>]) -> bool {
+        others
+            .iter()
+            .any(|other| self.bounds_overlap(other) && self.collides(other))
+    }
+
+    /// Indices into `others` of every shape `self` collides with, in order.
+    pub fn collides_which<U>(&self, others: &[Collidable<U>]) -> Vec<usize> {
+        others
+            .iter()
+            .enumerate()
+            .filter(|&(_, other)| self.bounds_overlap(other) && self.collides(other))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Cheap rejection before `collides`. Touching boxes overlap, since segments collide on
+    /// contact.
+    fn bounds_overlap<U>(&self, other: &Collidable<U>) -> bool {
+        let (a, b) = (self.aabb(), other.aabb());
+        let m = self.margin + other.margin;
+        a.0 - m <= b.2 && b.0 <= a.2 + m && a.1 - m <= b.3 && b.1 <= a.3 + m
+    }
+
+    /// Like `collides`, but shapes less than `epsilon` apart also count, so resting contacts
+    /// don't flicker. An `epsilon` of 0 gives exactly `collides`.
+    pub fn collides_within<U>(&self, other: &Collidable<U>, epsilon: Real) -> bool {
+        self.collides(other) || self.distance(other) < epsilon
+    }
+
+    fn collides_capsule<U>(&self, capsule: &Collidable<U>) -> bool {
+        let (p, q) = self.closest_core_points(capsule);
+        dist_sqrdf(p.x, p.y, q.x, q.y) < sqrf(capsule.radius)
+            || poly_contains_pnt(
+                self.nvert,
+                &self.vertx,
+                &self.verty,
+                capsule.vertx[0],
+                capsule.verty[0],
+            )
+    }
+
+    fn collides_circle_ellipse<U>(&self, circle: &Collidable<U>) -> bool {
+        let (px, py) = (circle.centrex - self.centrex, circle.centrey - self.centrey);
+        let (ex, ey) = closest_pnt_ellipse(self.width * 0.5, self.height * 0.5, px, py);
+        self.contains_point(circle.centrex, circle.centrey)
+            || dist_sqrdf(px, py, ex, ey) < sqrf(circle.radius)
+    }
+
+    fn collides_segment<U>(&self, segment: &Collidable<U>) -> bool {
+        let (s1, s2) = segment.core_edge(0);
+        for i in 0..self.nvert {
+            let (e1, e2) = self.core_edge(i);
+            if segments_intersect(s1, s2, e1, e2) {
+                return true;
+            }
+        }
+        poly_contains_pnt(self.nvert, &self.vertx, &self.verty, s1.x, s1.y)
+    }
+
+    /// The radius every point of the core shape is grown by.
+    fn rounding(&self) -> Real {
+        match self.collidable_shape {
+            CollidableShape::Polygon | CollidableShape::Segment | CollidableShape::Ellipse => 0.,
+            CollidableShape::Circle | CollidableShape::Capsule => self.radius,
+        }
+    }
+
+    /// The edges of the core shape, before rounding. A circle's core is a single point.
+    fn core_edge(&self, i: usize) -> (SupportPoint, SupportPoint) {
+        match self.collidable_shape {
+            CollidableShape::Circle | CollidableShape::Ellipse => {
+                let c = Vector2::new(self.centrex, self.centrey);
+                (c, c)
+            }
+            _ => {
+                let j = if i + 1 < self.nvert { i + 1 } else { 0 };
+                (
+                    Vector2::new(self.vertx[i], self.verty[i]),
+                    Vector2::new(self.vertx[j], self.verty[j]),
+                )
+            }
+        }
+    }
+
+    fn core_edge_count(&self) -> usize {
+        match self.collidable_shape {
+            CollidableShape::Circle
+            | CollidableShape::Capsule
+            | CollidableShape::Segment
+            | CollidableShape::Ellipse => 1,
+            CollidableShape::Polygon => self.nvert,
+        }
+    }
+
+    /// Nearest points between the boundaries of the two core shapes.
+    pub(crate) fn closest_core_points<U>(
+        &self,
+        other: &Collidable<U>,
+    ) -> (SupportPoint, SupportPoint) {
+        if let CollidableShape::Ellipse = self.collidable_shape {
+            return self.ellipse_polygon().closest_core_points(other);
+        }
+        if let CollidableShape::Ellipse = other.collidable_shape {
+            return self.closest_core_points(&other.ellipse_polygon());
+        }
+        let mut best = Real::MAX;
+        let mut points = (nalgebra::zero(), nalgebra::zero());
+        for i in 0..self.core_edge_count() {
+            let (a1, a2) = self.core_edge(i);
+            for j in 0..other.core_edge_count() {
+                let (b1, b2) = other.core_edge(j);
+                let (p, q) = closest_pnts_seg_seg(a1, a2, b1, b2);
+                let d = dist_sqrdf(p.x, p.y, q.x, q.y);
+                if d < best {
+                    best = d;
+                    points = (p, q);
+                }
+            }
+        }
+        points
+    }
+
+    /// The edge normals a SAT test should check, not normalized, skipping zero-length edges.
+    /// Circles and ellipses have none: a test involving one also needs the axis towards the
+    /// nearest point of the other shape, e.g. from a circle's centre to the closest vertex.
+    pub fn separating_axes(&self) -> Vec<(Real, Real)> {
+        (0..self.normx.len())
+            .map(|i| (self.normx[i], self.normy[i]))
+            .filter(|&(x, y)| x != 0. || y != 0.)
+            .collect()
+    }
+
+    /// The `(min, max)` of the shape's dot products with the axis, which needn't be unit
+    /// length. Two shapes are separated along the axis when these ranges don't overlap.
+    pub fn project_onto_axis(&self, ax: Real, ay: Real) -> (Real, Real) {
+        let (min, max) = match self.collidable_shape {
+            CollidableShape::Circle => {
+                let c = self.centrex * ax + self.centrey * ay;
+                (c, c)
+            }
+            CollidableShape::Ellipse => {
+                let c = self.centrex * ax + self.centrey * ay;
+                let h = (sqrf(self.width * 0.5 * ax) + sqrf(self.height * 0.5 * ay)).sqrt();
+                (c - h, c + h)
+            }
+            _ => project_poly(self.nvert, &self.vertx, &self.verty, ax, ay),
+        };
+        let r = self.rounding() * (ax * ax + ay * ay).sqrt();
+        (min - r, max + r)
+    }
+
+    fn collides_circle<U>(&self, circle: &Collidable<U>) -> bool {
+        poly_contains_pnt(
+            self.nvert,
+            &self.vertx,
+            &self.verty,
+            circle.centrex,
+            circle.centrey,
+        ) || dist_poly_circ(
+            self.nvert,
+            &self.vertx,
+            &self.verty,
+            circle.centrex,
+            circle.centrey,
+        ) < circle.radius
+    }
+
+    /// Returns `(min_x, min_y, max_x, max_y)`, cached from the vertices rather than derived
+    /// from `width`/`height`. The methods that move or reshape the collidable keep it up to
+    /// date; call `update_bounds` after editing the public fields directly.
+    pub fn aabb(&self) -> (Real, Real, Real, Real) {
+        self.cached_aabb
+    }
+
+    fn with_cached_aabb(mut self) -> Collidable<T> {
+        self.refresh_aabb();
+        self
+    }
+
+    fn refresh_aabb(&mut self) {
+        self.cached_aabb = self.compute_aabb();
+    }
+
+    fn compute_aabb(&self) -> (Real, Real, Real, Real) {
+        match self.collidable_shape {
+            CollidableShape::Circle => (
+                self.centrex - self.radius,
+                self.centrey - self.radius,
+                self.centrex + self.radius,
+                self.centrey + self.radius,
+            ),
+            CollidableShape::Ellipse => (
+                self.centrex - self.width * 0.5,
+                self.centrey - self.height * 0.5,
+                self.centrex + self.width * 0.5,
+                self.centrey + self.height * 0.5,
+            ),
+            CollidableShape::Polygon => poly_extents(self.nvert, &self.vertx, &self.verty),
+            CollidableShape::Capsule | CollidableShape::Segment => {
+                let (minx, miny, maxx, maxy) = poly_extents(self.nvert, &self.vertx, &self.verty);
+                let r = self.rounding();
+                (minx - r, miny - r, maxx + r, maxy + r)
+            }
+        }
+    }
+
+    /// Points exactly on the boundary count as inside. Polygons use a crossing-number test,
+    /// so the result doesn't depend on winding and holds for concave shapes.
+    pub fn contains_point(&self, x: Real, y: Real) -> bool {
+        match self.collidable_shape {
+            CollidableShape::Circle => {
+                dist_sqrdf(self.centrex, self.centrey, x, y) <= sqrf(self.radius)
+            }
+            CollidableShape::Polygon => {
+                poly_boundary_contains_pnt(self.nvert, &self.vertx, &self.verty, x, y)
+                    || poly_contains_pnt(self.nvert, &self.vertx, &self.verty, x, y)
+            }
+            CollidableShape::Ellipse => {
+                sqrf((x - self.centrex) / (self.width * 0.5))
+                    + sqrf((y - self.centrey) / (self.height * 0.5))
+                    <= 1.
+            }
+            CollidableShape::Capsule | CollidableShape::Segment => {
+                dist_line_pnt(
+                    self.vertx[0],
+                    self.verty[0],
+                    self.vertx[1],
+                    self.verty[1],
+                    x,
+                    y,
+                ) <= self.rounding()
+            }
+        }
+    }
+
+    /// True if every point of `other` lies inside `self`, boundary included. Concave
+    /// polygons are handled exactly.
+    ///
+    /// NOTE: Ellipses are approximated by their tessellation, as in `collides`.
+    pub fn contains<U>(&self, other: &Collidable<U>) -> bool {
+        if let CollidableShape::Ellipse = self.collidable_shape {
+            return self.ellipse_polygon().contains(other);
+        }
+        if let CollidableShape::Ellipse = other.collidable_shape {
+            return self.contains(&other.ellipse_polygon());
+        }
+        let (ocount, r) = (other.core_edge_count(), other.rounding());
+        match self.collidable_shape {
+            CollidableShape::Polygon => {
+                for i in 0..ocount {
+                    let (a, b) = other.core_edge(i);
+                    if !self.contains_point(a.x, a.y) || !self.contains_point(b.x, b.y) {
+                        return false;
+                    }
+                    for j in 0..self.nvert {
+                        let (p, q) = self.core_edge(j);
+                        if segments_cross(a, b, p, q) {
+                            return false;
+                        }
+                    }
+                }
+                if r > 0. {
+                    let (p, q) = self.closest_core_points(other);
+                    return dist_sqrdf(p.x, p.y, q.x, q.y) >= sqrf(r);
+                }
+                true
+            }
+            // Distance from the core is convex, so the farthest point of `other` is one of
+            // its core vertices pushed out by its rounding.
+            _ => {
+                let (p, q) = self.core_edge(0);
+                let reach = self.rounding() - r;
+                reach >= 0.
+                    && (0..ocount).all(|i| {
+                        let (a, b) = other.core_edge(i);
+                        [a, b].iter().all(|&v| {
+                            let (c, _) = closest_pnts_seg_seg(p, q, v, v);
+                            dist_sqrdf(c.x, c.y, v.x, v.y) <= sqrf(reach)
+                        })
+                    })
+            }
+        }
+    }
+
+    /// The area of the intersection of the two shapes, 0 when they're disjoint or only touch.
+    /// At least one of them must be convex; concave pairs are approximate.
+    ///
+    /// NOTE: Circles, ellipses and capsules are approximated by tessellated polygons.
+    pub fn overlap_area<U>(&self, other: &Collidable<U>) -> Real {
+        let (mut a, mut b) = match (self.area_polygon(), other.area_polygon()) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return 0.,
+        };
+        a.ensure_ccw();
+        b.ensure_ccw();
+        let (subject, clip) = if b.is_convex() { (&a, &b) } else { (&b, &a) };
+        let (x, y) = clip_poly(&subject.vertx, &subject.verty, &clip.vertx, &clip.verty);
+        poly_signed_area(x.len(), &x, &y).abs()
+    }
+
+    /// Counter-clockwise triangles covering the shape, for filled rendering. Polygons are
+    /// fanned when convex and ear clipped otherwise; circles and ellipses become a fan
+    /// around the centre, tessellated as in `overlap_area`. Segments have no area and give
+    /// no triangles.
+    pub fn triangulate(&self) -> Vec<[(Real, Real); 3]> {
+        match self.collidable_shape {
+            CollidableShape::Circle | CollidableShape::Ellipse => {
+                let centre = (self.centrex, self.centrey);
+                self.ellipse_polygon()
+                    .edges()
+                    .map(|(a, b)| [centre, a, b])
+                    .collect()
+            }
+            _ => match self.area_polygon() {
+                Some(poly) => triangulate_poly(poly.nvert, &poly.vertx, &poly.verty),
+                None => vec![],
+            },
+        }
+    }
+
+    /// The boundary as line segments for drawing. Curves are tessellated as in
+    /// `overlap_area`; a segment is its single edge.
+    pub fn outline(&self) -> Vec<((Real, Real), (Real, Real))> {
+        match self.area_polygon() {
+            Some(poly) => poly.edges().collect(),
+            None => self.edges().collect(),
+        }
+    }
+
+    /// This shape as a polygon with the same area, tessellating any curves. Segments have
+    /// no area.
+    fn area_polygon(&self) -> Option<Collidable> {
+        match self.collidable_shape {
+            CollidableShape::Polygon => Some(self.without_data()),
+            CollidableShape::Circle | CollidableShape::Ellipse => Some(self.ellipse_polygon()),
+            CollidableShape::Segment => None,
+            CollidableShape::Capsule => {
+                let (a, b) = self.core_edge(0);
+                let e = b - a;
+                let len = nalgebra::norm(&e);
+                if len == 0. {
+                    return Collidable::new_circle(0, 0, a.x, a.y, self.radius).area_polygon();
+                }
+                let side = Vector2::new(e.y, -e.x) * (self.radius / len);
+                let step = 2. * consts::PI / ELLIPSE_SEGMENTS as Real;
+                let (mut vertx, mut verty) = (vec![], vec![]);
+                push_arc(&mut vertx, &mut verty, b, side, consts::PI, step);
+                push_arc(&mut vertx, &mut verty, a, -side, consts::PI, step);
+                Some(Collidable::build_poly(
+                    self.collidable_type,
+                    self.collidable_id,
+                    vertx.len(),
+                    vertx,
+                    verty,
+                ))
+            }
+        }
+    }
+
+    /// The gap between the two shapes, exactly 0 when they collide. Use `collision_mtv` for
+    /// penetration depth.
+    pub fn distance<U>(&self, other: &Collidable<U>) -> Real {
+        if self.collides_core(other) {
+            return 0.;
+        }
+        let gap = match (self.collidable_shape, other.collidable_shape) {
+            (CollidableShape::Ellipse, CollidableShape::Circle) => {
+                let (x, y) = self.nearest_point(other.centrex, other.centrey);
+                dist_sqrdf(x, y, other.centrex, other.centrey).sqrt() - other.radius
+            }
+            (CollidableShape::Circle, CollidableShape::Ellipse) => other.distance(self),
+            _ => {
+                let (p, q) = self.closest_core_points(other);
+                dist_sqrdf(p.x, p.y, q.x, q.y).sqrt() - self.rounding() - other.rounding()
+            }
+        };
+        gap.max(0.)
+    }
+
+    /// The point on `self` and the point on `other` nearest each other, so their distance is
+    /// `distance`. Margins are ignored. Colliding shapes give the same point twice, the
+    /// middle of the `manifold` contacts.
+    pub fn closest_points<U>(&self, other: &Collidable<U>) -> ((Real, Real), (Real, Real)) {
+        if let Some(m) = self.core_manifold(other) {
+            let n = m.contacts.len() as Real;
+            let x = m.contacts.iter().map(|c| c.0).sum::<Real>() / n;
+            let y = m.contacts.iter().map(|c| c.1).sum::<Real>() / n;
+            return ((x, y), (x, y));
+        }
+        match (self.collidable_shape, other.collidable_shape) {
+            (CollidableShape::Ellipse, CollidableShape::Circle) => {
+                let p = self.nearest_point(other.centrex, other.centrey);
+                let c = Vector2::new(other.centrex, other.centrey);
+                let q = c + nalgebra::normalize(&(Vector2::new(p.0, p.1) - c)) * other.radius;
+                (p, (q.x, q.y))
+            }
+            (CollidableShape::Circle, CollidableShape::Ellipse) => {
+                let (q, p) = other.closest_points(self);
+                (p, q)
+            }
+            _ => {
+                let (p, q) = self.closest_core_points(other);
+                let d = q - p;
+                let len = nalgebra::norm(&d);
+                if len == 0. {
+                    return ((p.x, p.y), (q.x, q.y));
+                }
+                let n = d / len;
+                let (p, q) = (p + n * self.rounding(), q - n * other.rounding());
+                ((p.x, p.y), (q.x, q.y))
+            }
+        }
+    }
+
+    /// The closest point on the shape's boundary. Points inside the shape also map to the
+    /// boundary, so this is never the query point itself unless it lies on the boundary.
+    pub fn nearest_point(&self, x: Real, y: Real) -> (Real, Real) {
+        let p = Vector2::new(x, y);
+        match self.collidable_shape {
+            CollidableShape::Ellipse => {
+                let (ex, ey) = closest_pnt_ellipse(
+                    self.width * 0.5,
+                    self.height * 0.5,
+                    x - self.centrex,
+                    y - self.centrey,
+                );
+                (self.centrex + ex, self.centrey + ey)
+            }
+            _ => {
+                let mut best = Real::MAX;
+                let mut closest = p;
+                for i in 0..self.core_edge_count() {
+                    let (a, b) = self.core_edge(i);
+                    let (q, _) = closest_pnts_seg_seg(a, b, p, p);
+                    let d = dist_sqrdf(q.x, q.y, x, y);
+                    if d < best {
+                        best = d;
+                        closest = q;
+                    }
+                }
+                let r = self.rounding();
+                if r > 0. {
+                    // A point on the core has no direction out, so push along +x.
+                    let offset = if best > 0. {
+                        nalgebra::normalize(&(p - closest))
+                    } else {
+                        Vector2::new(1., 0.)
+                    };
+                    closest += offset * r;
+                }
+                (closest.x, closest.y)
+            }
+        }
+    }
+
+    /// Unit outward normal of the boundary nearest to `(x, y)`, such as a contact point. For
+    /// polygons it's the normal of the nearest edge, the lower index on a tie. A point at the
+    /// centre of a circle or on a segment's spine gets an arbitrary but valid normal.
+    pub fn surface_normal_at(&self, x: Real, y: Real) -> (Real, Real) {
+        let p = Vector2::new(x, y);
+        let n = match self.collidable_shape {
+            CollidableShape::Polygon => self.outward_normal(self.nearest_core_edge(p)),
+            CollidableShape::Ellipse => {
+                let (qx, qy) = self.nearest_point(x, y);
+                let (rx, ry) = (self.width * 0.5, self.height * 0.5);
+                let g = Vector2::new(
+                    (qx - self.centrex) / (rx * rx),
+                    (qy - self.centrey) / (ry * ry),
+                );
+                nalgebra::normalize(&g)
+            }
+            _ => {
+                let (a, b) = self.core_edge(self.nearest_core_edge(p));
+                let (q, _) = closest_pnts_seg_seg(a, b, p, p);
+                if p != q {
+                    nalgebra::normalize(&(p - q))
+                } else if a != b {
+                    nalgebra::normalize(&Vector2::new(b.y - a.y, a.x - b.x))
+                } else {
+                    Vector2::new(1., 0.)
+                }
+            }
+        };
+        (n.x, n.y)
+    }
+
+    /// The velocity `(vx, vy)` bounced off the surface at the contact point `(x, y)`.
+    pub fn reflect_velocity(&self, vx: Real, vy: Real, x: Real, y: Real) -> (Real, Real) {
+        let (nx, ny) = self.surface_normal_at(x, y);
+        reflect(vx, vy, nx, ny)
+    }
+
+    /// Index of the edge nearest to `(x, y)`, matching `normx[i]`/`normy[i]`, the lower index
+    /// on a tie. Capsules and segments have the single edge 0; circles and ellipses none.
+    pub fn nearest_edge(&self, x: Real, y: Real) -> Option<usize> {
+        match self.collidable_shape {
+            CollidableShape::Circle | CollidableShape::Ellipse => None,
+            _ => Some(self.nearest_core_edge(Vector2::new(x, y))),
+        }
+    }
+
+    /// Index of the core edge nearest to `p`, the lower one on a tie.
+    fn nearest_core_edge(&self, p: SupportPoint) -> usize {
+        let mut best = (0, Real::MAX);
+        for i in 0..self.core_edge_count() {
+            let (a, b) = self.core_edge(i);
+            let (q, _) = closest_pnts_seg_seg(a, b, p, p);
+            let d = dist_sqrdf(q.x, q.y, p.x, p.y);
+            if d < best.1 {
+                best = (i, d);
+            }
+        }
+        best.0
+    }
+
+    /// Casts a ray from `(ox, oy)` along `(dx, dy)`, which needn't be normalized; `distance`
+    /// is measured in world units. Tangent rays count as hits.
+    ///
+    /// A ray starting inside (or on) the shape hits immediately: distance 0 at the origin,
+    /// with the normal pointing back along the ray; see `raycast_exit` for where it leaves.
+    /// Zero-length directions never hit.
+    pub fn raycast(&self, ox: Real, oy: Real, dx: Real, dy: Real) -> Option<RayHit> {
+        let len = (dx * dx + dy * dy).sqrt();
+        if len == 0. {
+            return None;
+        }
+        let o = Vector2::new(ox, oy);
+        let d = Vector2::new(dx / len, dy / len);
+        if self.contains_point(ox, oy) {
+            return Some(RayHit {
+                distance: 0.,
+                point: (ox, oy),
+                normal: (-d.x, -d.y),
+            });
+        }
+
+        let (t, n) = match self.collidable_shape {
+            CollidableShape::Circle => {
+                let c = Vector2::new(self.centrex, self.centrey);
+                let t = ray_circle(o, d, c, self.radius)?;
+                (t, (o + d * t - c) / self.radius)
+            }
+            CollidableShape::Ellipse => {
+                let (rx, ry) = (self.width * 0.5, self.height * 0.5);
+                let m = Vector2::new((ox - self.centrex) / rx, (oy - self.centrey) / ry);
+                let e = Vector2::new(d.x / rx, d.y / ry);
+                let a = nalgebra::dot(&e, &e);
+                let b = nalgebra::dot(&m, &e);
+                let disc = b * b - a * (nalgebra::dot(&m, &m) - 1.);
+                if disc < 0. {
+                    return None;
+                }
+                let t = (-b - disc.sqrt()) / a;
+                if t < 0. {
+                    return None;
+                }
+                let p = m + e * t;
+                (t, nalgebra::normalize(&Vector2::new(p.x / rx, p.y / ry)))
+            }
+            CollidableShape::Polygon => {
+                let mut best: Option<(Real, usize)> = None;
+                for i in 0..self.nvert {
+                    let (p, q) = self.core_edge(i);
+                    if let Some(t) = ray_segment(o, d, p, q) {
+                        if best.is_none_or(|(b, _)| t < b) {
+                            best = Some((t, i));
+                        }
+                    }
+                }
+                let (t, i) = best?;
+                let n = nalgebra::normalize(&Vector2::new(self.normx[i], self.normy[i]));
+                // Clockwise polygons have inward normals.
+                (t, if nalgebra::dot(&n, &d) > 0. { -n } else { n })
+            }
+            CollidableShape::Segment => {
+                let (p, q) = self.core_edge(0);
+                let t = ray_segment(o, d, p, q)?;
+                let e = q - p;
+                let n = if e.x == 0. && e.y == 0. {
+                    -d
+                } else {
+                    nalgebra::normalize(&Vector2::new(e.y, -e.x))
+                };
+                (t, if nalgebra::dot(&n, &d) > 0. { -n } else { n })
+            }
+            CollidableShape::Capsule => {
+                let (p, q) = self.core_edge(0);
+                let r = self.radius;
+                let mut best: Option<(Real, SupportPoint)> = None;
+                for &c in &[p, q] {
+                    if let Some(t) = ray_circle(o, d, c, r) {
+                        if best.is_none_or(|(b, _)| t < b) {
+                            best = Some((t, (o + d * t - c) / r));
+                        }
+                    }
+                }
+                let e = q - p;
+                if e.x != 0. || e.y != 0. {
+                    let n = nalgebra::normalize(&Vector2::new(e.y, -e.x));
+                    for &n in &[n, -n] {
+                        if let Some(t) = ray_segment(o, d, p + n * r, q + n * r) {
+                            if best.is_none_or(|(b, _)| t < b) {
+                                best = Some((t, n));
+                            }
+                        }
+                    }
+                }
+                best?
+            }
+        };
+        Some(RayHit {
+            distance: t,
+            point: (ox + d.x * t, oy + d.y * t),
+            normal: (n.x, n.y),
+        })
+    }
+
+    /// Where a ray starting inside (or on) the shape leaves it, with the outward normal of
+    /// the boundary there and `distance` in world units. None for origins outside, for
+    /// segments, which have no inside, and for zero-length directions.
+    pub fn raycast_exit(&self, ox: Real, oy: Real, dx: Real, dy: Real) -> Option<RayHit> {
+        let len = (dx * dx + dy * dy).sqrt();
+        if len == 0. || !self.contains_point(ox, oy) {
+            return None;
+        }
+        let o = Vector2::new(ox, oy);
+        let d = Vector2::new(dx / len, dy / len);
+
+        let (t, n) = match self.collidable_shape {
+            CollidableShape::Circle => {
+                let c = Vector2::new(self.centrex, self.centrey);
+                let t = ray_circle_exit(o, d, c, self.radius)?;
+                (t, (o + d * t - c) / self.radius)
+            }
+            CollidableShape::Ellipse => {
+                let (rx, ry) = (self.width * 0.5, self.height * 0.5);
+                let m = Vector2::new((ox - self.centrex) / rx, (oy - self.centrey) / ry);
+                let e = Vector2::new(d.x / rx, d.y / ry);
+                let a = nalgebra::dot(&e, &e);
+                let b = nalgebra::dot(&m, &e);
+                let disc = b * b - a * (nalgebra::dot(&m, &m) - 1.);
+                let t = (-b + disc.max(0.).sqrt()) / a;
+                let p = m + e * t;
+                (t, nalgebra::normalize(&Vector2::new(p.x / rx, p.y / ry)))
+            }
+            CollidableShape::Polygon => {
+                // The nearest edge crossed on the way out, which also handles concave shapes
+                // and origins on an edge facing the other way.
+                let mut best: Option<(Real, SupportPoint)> = None;
+                for i in 0..self.nvert {
+                    let n = self.outward_normal(i);
+                    if nalgebra::dot(&n, &d) <= 0. {
+                        continue;
+                    }
+                    let (p, q) = self.core_edge(i);
+                    if let Some(t) = ray_segment(o, d, p, q) {
+                        if best.is_none_or(|(b, _)| t < b) {
+                            best = Some((t, n));
+                        }
+                    }
+                }
+                best?
+            }
+            CollidableShape::Segment => return None,
+            CollidableShape::Capsule => {
+                // A capsule is convex, so the ray leaves it where the last of its two end
+                // circles and its side strip does.
+                let (p, q) = self.core_edge(0);
+                let r = self.radius;
+                let mut best: Option<(Real, SupportPoint)> = None;
+                for &c in &[p, q] {
+                    if let Some(t) = ray_circle_exit(o, d, c, r) {
+                        if best.is_none_or(|(b, _)| t > b) {
+                            best = Some((t, (o + d * t - c) / r));
+                        }
+                    }
+                }
+                let e = q - p;
+                if e.x != 0. || e.y != 0. {
+                    let n = nalgebra::normalize(&Vector2::new(e.y, -e.x));
+                    for &n in &[n, -n] {
+                        if nalgebra::dot(&n, &d) <= 0. {
+                            continue;
+                        }
+                        if let Some(t) = ray_segment(o, d, p + n * r, q + n * r) {
+                            if best.is_none_or(|(b, _)| t > b) {
+                                best = Some((t, n));
+                            }
+                        }
+                    }
+                }
+                best?
+            }
+        };
+        Some(RayHit {
+            distance: t,
+            point: (ox + d.x * t, oy + d.y * t),
+            normal: (n.x, n.y),
+        })
+    }
+
+    /// The fraction of a step, in `[0, 1]`, at which two shapes moving by `self_vel` and
+    /// `other_vel` first come within a small tolerance of each other, found by conservative
+    /// advancement. Shapes already colliding or touching return 0.
+    ///
+    /// Returns None if they never get that close during the step, including shapes that
+    /// only slide past one another with no closing speed. Each advance is safe for convex
+    /// shapes; concave polygons can step past a contact.
+    pub fn time_of_impact<U>(
+        &self,
+        self_vel: (Real, Real),
+        other: &Collidable<U>,
+        other_vel: (Real, Real),
+    ) -> Option<Real> {
+        // Only the relative motion matters, so move self and hold other still.
+        let v = Vector2::new(self_vel.0 - other_vel.0, self_vel.1 - other_vel.1);
+        let mut moved = self.without_data();
+        let mut t = 0.;
+        for _ in 0..TOI_ITERATIONS {
+            if moved.collides(other) {
+                return Some(t);
+            }
+            let (p, q) = moved.closest_core_points(other);
+            let d = q - p;
+            let len = nalgebra::norm(&d);
+            let gap = len - moved.rounding() - other.rounding();
+            if gap <= TOI_TOLERANCE || len == 0. {
+                return Some(t);
+            }
+            let closing = nalgebra::dot(&v, &(d / len));
+            if closing <= 0. {
+                return None;
+            }
+            let dt = gap / closing;
+            if t + dt > 1. {
+                return None;
+            }
+            t += dt;
+            moved.translate(v.x * dt, v.y * dt);
+        }
+        Some(t)
+    }
+
+    /// Raycast against the core shape grown by `r`, for an origin outside it.
+    fn raycast_rounded(&self, o: SupportPoint, d: SupportPoint, r: Real) -> Option<Real> {
+        let mut best: Option<Real> = None;
+        let mut keep = |t: Option<Real>| {
+            if let Some(t) = t {
+                if best.is_none_or(|b| t < b) {
+                    best = Some(t);
+                }
+            }
+        };
+        for i in 0..self.core_edge_count() {
+            let (p, q) = self.core_edge(i);
+            keep(ray_circle(o, d, p, r));
+            keep(ray_circle(o, d, q, r));
+            let e = q - p;
+            if e.x != 0. || e.y != 0. {
+                let n = nalgebra::normalize(&Vector2::new(e.y, -e.x)) * r;
+                keep(ray_segment(o, d, p + n, q + n));
+                keep(ray_segment(o, d, p - n, q - n));
+            }
+        }
+        best
+    }
+
+    /// The point of the shape farthest along `(dx, dy)`. Ties between polygon vertices go to
+    /// the first. A zero direction gives a point on the core.
+    pub fn support(&self, dx: Real, dy: Real) -> (Real, Real) {
+        let p = self.support_vec(Vector2::new(dx, dy));
+        (p.x, p.y)
+    }
+
+    fn support_vec(&self, d: SupportPoint) -> SupportPoint {
+        let len = nalgebra::norm(&d);
+        let centre = Vector2::new(self.centrex, self.centrey);
+        if let CollidableShape::Ellipse = self.collidable_shape {
+            let (rx, ry) = (self.width * 0.5, self.height * 0.5);
+            let k = (sqrf(rx * d.x) + sqrf(ry * d.y)).sqrt();
+            if k == 0. {
+                return centre;
+            }
+            return centre + Vector2::new(rx * rx * d.x, ry * ry * d.y) / k;
+        }
+        let core = match self.collidable_shape {
+            CollidableShape::Circle => centre,
+            _ => support_pnt(self.nvert, &self.vertx, &self.verty, d),
+        };
+        if len == 0. {
+            core
+        } else {
+            core + d * (self.rounding() / len)
+        }
+    }
+
+    /// The convex polygon `self ⊖ other`, built from the convex hull of every vertex of
+    /// `self` minus every vertex of `other`, with type and id from `self`. The origin lies
+    /// strictly inside it exactly when two convex polygons collide.
+    ///
+    /// NOTE: Ellipses are tessellated, and circles and capsules contribute only their core,
+    /// so their rounding is lost. A degenerate result comes back as in `from_points`.
+    pub fn minkowski_difference<U>(&self, other: &Collidable<U>) -> Collidable {
+        let (a, b) = (self.core_points(), other.core_points());
+        let mut points = Vec::with_capacity(a.len() * b.len());
+        for p in &a {
+            for q in &b {
+                points.push((p.x - q.x, p.y - q.y));
+            }
+        }
+        Collidable::from_points(self.collidable_type, self.collidable_id, &points)
+    }
+
+    fn core_points(&self) -> Vec<SupportPoint> {
+        match self.collidable_shape {
+            CollidableShape::Circle => vec![Vector2::new(self.centrex, self.centrey)],
+            CollidableShape::Ellipse => self.ellipse_polygon().vertices().collect(),
+            _ => self.vertices().collect(),
+        }
+    }
+
+    /// GJK over the support functions. Exact for every convex shape, including circles,
+    /// capsules and ellipses without tessellation; concave polygons are treated as their
+    /// convex hull. Like `collides`, shapes that only touch don't intersect.
+    pub fn gjk_intersects<U>(&self, other: &Collidable<U>) -> bool {
+        self.gjk_simplex(other).is_some()
+    }
+
+    /// Penetration found by expanding the GJK triangle (EPA), as the translation that
+    /// pushes `self` out of `other` like `collision_mtv`. `None` when the shapes don't overlap.
+    /// For curved shapes the depth is approximate, to within a small tolerance.
+    pub fn epa_penetration<U>(&self, other: &Collidable<U>) -> Option<(Real, Real)> {
+        let mut polytope = self.gjk_simplex(other)?;
+        let a = polytope[1] - polytope[0];
+        let b = polytope[2] - polytope[0];
+        if a.x * b.y - a.y * b.x < 0. {
+            polytope.swap(1, 2);
+        }
+        let mut best = None;
+        for _ in 0..EPA_ITERATIONS {
+            // The edge of the counter-clockwise polytope nearest the origin.
+            let mut nearest: Option<(usize, SupportPoint, Real)> = None;
+            for i in 0..polytope.len() {
+                let (p, q) = (polytope[i], polytope[(i + 1) % polytope.len()]);
+                let e = q - p;
+                let len = nalgebra::norm(&e);
+                if len == 0. {
+                    continue;
+                }
+                let n = Vector2::new(e.y, -e.x) / len;
+                let dist = nalgebra::dot(&n, &p);
+                if nearest.is_none_or(|(_, _, d)| dist < d) {
+                    nearest = Some((i, n, dist));
+                }
+            }
+            let (i, n, dist) = nearest?;
+            best = Some((-n.x * dist, -n.y * dist));
+            let p = self.minkowski_support(other, n);
+            if nalgebra::dot(&p, &n) - dist < EPA_TOLERANCE {
+                break;
+            }
+            polytope.insert(i + 1, p);
+        }
+        best
+    }
+
+    /// A point of the Minkowski difference `self - other`.
+    fn minkowski_support<U>(&self, other: &Collidable<U>, d: SupportPoint) -> SupportPoint {
+        self.support_vec(d) - other.support_vec(-d)
+    }
+
+    /// The triangle of the Minkowski difference enclosing the origin, if the shapes overlap.
+    fn gjk_simplex<U>(&self, other: &Collidable<U>) -> Option<Vec<SupportPoint>> {
+        let mut d = Vector2::new(other.centrex - self.centrex, other.centrey - self.centrey);
+        if d.x == 0. && d.y == 0. {
+            d = Vector2::new(1., 0.);
+        }
+        let first = self.minkowski_support(other, d);
+        if first.x == 0. && first.y == 0. {
+            return None;
+        }
+        let mut simplex = vec![first];
+        d = -first;
+        for _ in 0..GJK_ITERATIONS {
+            let p = self.minkowski_support(other, d);
+            if nalgebra::dot(&p, &d) <= 0. {
+                return None;
+            }
+            simplex.push(p);
+            if simplex.len() == 2 {
+                // The origin lies between the two points, on the same side as `d` was.
+                let ab = simplex[0] - simplex[1];
+                let perp = Vector2::new(-ab.y, ab.x);
+                d = if nalgebra::dot(&perp, &-simplex[1]) < 0. {
+                    -perp
+                } else {
+                    perp
+                };
+                continue;
+            }
+            // Find an edge of the triangle with the origin strictly outside it.
+            let mut on_edge = None;
+            let mut outside = None;
+            for &(i, j, k) in &[(2, 1, 0), (2, 0, 1), (1, 0, 2)] {
+                let e = simplex[j] - simplex[i];
+                let mut n = Vector2::new(-e.y, e.x);
+                if nalgebra::dot(&n, &(simplex[k] - simplex[i])) > 0. {
+                    n = -n;
+                }
+                let side = nalgebra::dot(&n, &-simplex[i]);
+                if side > 0. {
+                    outside = Some((i, j, n));
+                    break;
+                } else if side == 0. {
+                    on_edge = Some(n);
+                }
+            }
+            match (outside, on_edge) {
+                (Some((i, j, n)), _) => {
+                    simplex = vec![simplex[j], simplex[i]];
+                    d = n;
+                }
+                // On an edge the origin is only inside if the difference reaches past it.
+                (None, Some(n)) => {
+                    let p = self.minkowski_support(other, n);
+                    return if nalgebra::dot(&p, &n) > 0. {
+                        Some(simplex)
+                    } else {
+                        None
+                    };
+                }
+                (None, None) => return Some(simplex),
+            }
+        }
+        None
+    }
+
+    /// The contact manifold, built on `collision_mtv`. Polygon pairs clip the incident edge
+    /// against the reference face for one or two contacts on the incident shape. Pairs with
+    /// a circle give the single deepest point of the circle's surface; other pairs give the
+    /// midpoint of each shape's deepest point. A segment that only touches gives a single
+    /// contact at depth 0.
+    pub fn manifold<U>(&self, other: &Collidable<U>) -> Option<Manifold> {
+        let margin = self.margin + other.margin;
+        if let Some(mut m) = self.core_manifold(other) {
+            m.depth += margin;
+            return Some(m);
+        }
+        if margin <= 0. {
+            return None;
+        }
+        let (p, q) = self.closest_core_points(other);
+        let d = q - p;
+        let len = nalgebra::norm(&d);
+        let gap = len - self.rounding() - other.rounding();
+        if len == 0. || gap >= margin {
+            return None;
+        }
+        let n = d / len;
+        let contact = (p + n * self.rounding() + q - n * other.rounding()) * 0.5;
+        Some(Manifold {
+            normal: (n.x, n.y),
+            depth: margin - gap,
+            contacts: vec![(contact.x, contact.y)],
+        })
+    }
+
+    /// `manifold` on the bare geometry, ignoring margins.
+    fn core_manifold<U>(&self, other: &Collidable<U>) -> Option<Manifold> {
+        match (self.collidable_shape, other.collidable_shape) {
+            (CollidableShape::Ellipse, _) => return self.ellipse_polygon().core_manifold(other),
+            (_, CollidableShape::Ellipse) => return self.core_manifold(&other.ellipse_polygon()),
+            _ => {}
+        }
+        let (mx, my) = self.core_mtv(other)?;
+        let depth = (mx * mx + my * my).sqrt();
+        if depth == 0. {
+            return self.touching_manifold(other);
+        }
+        let n = Vector2::new(-mx / depth, -my / depth);
+        let contacts = match (self.collidable_shape, other.collidable_shape) {
+            (CollidableShape::Polygon, CollidableShape::Polygon) => self.clip_contacts(other, n),
+            (CollidableShape::Circle, _) => vec![self.support_vec(n)],
+            (_, CollidableShape::Circle) => vec![other.support_vec(-n)],
+            _ => vec![(self.support_vec(n) + other.support_vec(-n)) * 0.5],
+        };
+        Some(Manifold {
+            normal: (n.x, n.y),
+            depth,
+            contacts: contacts.iter().map(|p| (p.x, p.y)).collect(),
+        })
+    }
+
+    /// Zero-depth manifold for a segment touching another shape, with the contact at the
+    /// touching point. The normal is across whichever segment lies more squarely between the
+    /// centres, so a T-junction pushes along the stem, facing from `self` to `other`.
+    fn touching_manifold<U>(&self, other: &Collidable<U>) -> Option<Manifold> {
+        let towards = Vector2::new(other.centrex - self.centrex, other.centrey - self.centrey);
+        let mut best: Option<(SupportPoint, Real)> = None;
+        for &(shape, nx, ny) in &[
+            (self.collidable_shape, self.normx[0], self.normy[0]),
+            (other.collidable_shape, other.normx[0], other.normy[0]),
+        ] {
+            let n = Vector2::new(nx, ny);
+            let len = nalgebra::norm(&n);
+            if shape != CollidableShape::Segment || len == 0. {
+                continue;
+            }
+            let n = n / len;
+            let d = nalgebra::dot(&n, &towards);
+            let (n, d) = if d < 0. { (-n, -d) } else { (n, d) };
+            match best {
+                Some((_, e)) if e >= d => {}
+                _ => best = Some((n, d)),
+            }
+        }
+        let (n, _) = best?;
+        let (p, q) = self.closest_core_points(other);
+        let contact = (p + q) * 0.5;
+        Some(Manifold {
+            normal: (n.x, n.y),
+            depth: 0.,
+            contacts: vec![(contact.x, contact.y)],
+        })
+    }
+
+    /// Unit outward normal of edge `i`, whatever the winding.
+    fn outward_normal(&self, i: usize) -> SupportPoint {
+        let n = Vector2::new(self.normx[i], self.normy[i]);
+        let len = nalgebra::norm(&n);
+        let sign = match self.winding() {
+            Winding::Clockwise => -1.,
+            Winding::CounterClockwise => 1.,
+        };
+        if len == 0. {
+            n
+        } else {
+            n * (sign / len)
+        }
+    }
+
+    /// The edge whose outward normal is most aligned with `n`.
+    fn face_towards(&self, n: SupportPoint) -> (usize, Real) {
+        let mut best = (0, Real::MIN);
+        for i in 0..self.nvert {
+            let d = nalgebra::dot(&self.outward_normal(i), &n);
+            if d > best.1 {
+                best = (i, d);
+            }
+        }
+        best
+    }
+
+    /// Polygon contacts for the collision normal `n` from `self` to `other`.
+    fn clip_contacts<U>(&self, other: &Collidable<U>, n: SupportPoint) -> Vec<SupportPoint> {
+        let (face_a, align_a) = self.face_towards(n);
+        let (face_b, align_b) = other.face_towards(-n);
+        // Prefer self's face unless other's is clearly better aligned.
+        let ((v1, v2), rn, (p1, p2)) = if align_b > align_a + 1e-3 {
+            let rn = other.outward_normal(face_b);
+            let (inc_face, _) = self.face_towards(-rn);
+            (other.core_edge(face_b), rn, self.core_edge(inc_face))
+        } else {
+            let rn = self.outward_normal(face_a);
+            let (inc_face, _) = other.face_towards(-rn);
+            (self.core_edge(face_a), rn, other.core_edge(inc_face))
+        };
+        let t = v2 - v1;
+        let len = nalgebra::norm(&t);
+        if len == 0. {
+            return vec![p1];
+        }
+        let t = t / len;
+
+        // Clip to the sides of the reference face, then keep what's behind it.
+        let mut points = vec![p1, p2];
+        for &(dir, offset) in &[(-t, -nalgebra::dot(&t, &v1)), (t, nalgebra::dot(&t, &v2))] {
+            let d: Vec<Real> = points
+                .iter()
+                .map(|p| nalgebra::dot(&dir, p) - offset)
+                .collect();
+            let mut clipped = vec![];
+            for k in 0..points.len() {
+                if d[k] <= 0. {
+                    clipped.push(points[k]);
+                }
+            }
+            if points.len() == 2 && d[0] * d[1] < 0. {
+                let s = d[0] / (d[0] - d[1]);
+                clipped.push(points[0] + (points[1] - points[0]) * s);
+            }
+            points = clipped;
+        }
+        points
+            .into_iter()
+            .filter(|p| nalgebra::dot(&rn, &(*p - v1)) <= 0.)
+            .collect()
+    }
+
+    /// Pushes two colliding shapes apart along `collision_mtv`, each moving half the way, so
+    /// they end up touching, or just their margins if they have any. Does nothing if they
+    /// don't collide.
+    pub fn resolve<U>(&mut self, other: &mut Collidable<U>) {
+        self.resolve_weighted(other, 1., 1.);
+    }
+
+    /// Like `resolve`, but the push is split by mass so the heavier shape moves less. The
+    /// masses must be positive.
+    pub fn resolve_weighted<U>(&mut self, other: &mut Collidable<U>, m1: Real, m2: Real) {
+        if let Some((mx, my)) = self.collision_mtv(other) {
+            let share = m2 / (m1 + m2);
+            self.translate(mx * share, my * share);
+            other.translate(-mx * (1. - share), -my * (1. - share));
+        }
+    }
+
+    /// Returns the minimum translation vector that pushes `self` out of `other`, or `None`
+    /// when the shapes aren't colliding. Pairs involving polygons, capsules or segments use
+    /// the SAT axis of least overlap. Segments that only touch give a zero vector.
+    ///
+    /// Margins count as in `collides`, so the push also opens a gap of the summed margins.
+    pub fn collision_mtv<U>(&self, other: &Collidable<U>) -> Option<(Real, Real)> {
+        if self.margin + other.margin <= 0. {
+            return self.core_mtv(other);
+        }
+        let m = self.manifold(other)?;
+        Some((-m.normal.0 * m.depth, -m.normal.1 * m.depth))
+    }
+
+    /// `collision_mtv` on the bare geometry, ignoring margins.
+    fn core_mtv<U>(&self, other: &Collidable<U>) -> Option<(Real, Real)> {
+        match (self.collidable_shape, other.collidable_shape) {
+            (CollidableShape::Ellipse, _) => self.ellipse_polygon().core_mtv(other),
+            (_, CollidableShape::Ellipse) => self.core_mtv(&other.ellipse_polygon()),
+            (CollidableShape::Polygon, CollidableShape::Polygon) => self.sat_mtv(other),
+            (CollidableShape::Circle, CollidableShape::Circle) => {
+                let dx = self.centrex - other.centrex;
+                let dy = self.centrey - other.centrey;
+                let d = (dx * dx + dy * dy).sqrt();
+                let depth = self.radius + other.radius - d;
+                if depth <= 0. {
+                    return None;
+                }
+                // Concentric circles have no preferred direction, so push along +y.
+                if d > 0. {
+                    Some((dx / d * depth, dy / d * depth))
+                } else {
+                    Some((0., depth))
+                }
+            }
+            (CollidableShape::Circle, CollidableShape::Polygon) => other.circle_mtv(self),
+            (CollidableShape::Polygon, CollidableShape::Circle) => {
+                self.circle_mtv(other).map(|(x, y)| (-x, -y))
+            }
+            (CollidableShape::Capsule, CollidableShape::Polygon) => other.capsule_mtv(self),
+            (CollidableShape::Polygon, CollidableShape::Capsule) => {
+                self.capsule_mtv(other).map(|(x, y)| (-x, -y))
+            }
+            (CollidableShape::Segment, _) | (_, CollidableShape::Segment) => {
+                // Segments collide on contact, where SAT finds no overlap to push out of.
+                match self.sat_mtv(other) {
+                    None if self.collides_core(other) => Some((0., 0.)),
+                    mtv => mtv,
+                }
+            }
+            (CollidableShape::Capsule, _) | (_, CollidableShape::Capsule) => self.sat_mtv(other),
+        }
+    }
+
+    /// MTV pushing `capsule` off this polygon. While the spine stays outside, the push is
+    /// along the line from the nearest point of the polygon, edge or vertex, to the spine,
+    /// so a capsule resting on a corner is pushed straight away from that vertex. A spine
+    /// reaching inside or crossing an edge falls back to SAT.
+    fn capsule_mtv<U>(&self, capsule: &Collidable<U>) -> Option<(Real, Real)> {
+        let (p, q) = self.closest_core_points(capsule);
+        let d = dist_sqrdf(p.x, p.y, q.x, q.y).sqrt();
+        let inside = (0..2).any(|k| {
+            poly_contains_pnt(
+                self.nvert,
+                &self.vertx,
+                &self.verty,
+                capsule.vertx[k],
+                capsule.verty[k],
+            )
+        });
+        // A tiny nonzero `d` can come from a spine crossing an edge with both ends outside,
+        // where pushing along it wouldn't separate the shapes.
+        let (s1, s2) = capsule.core_edge(0);
+        let crosses = (0..self.nvert).any(|i| {
+            let (a, b) = self.core_edge(i);
+            segments_intersect(a, b, s1, s2)
+        });
+        if inside || crosses || d == 0. {
+            return capsule.sat_mtv(self);
+        }
+        if d >= capsule.radius {
+            return None;
+        }
+        let push = (capsule.radius - d) / d;
+        Some(((q.x - p.x) * push, (q.y - p.y) * push))
+    }
+
+    /// MTV pushing `circle` out of this polygon.
+    fn circle_mtv<U>(&self, circle: &Collidable<U>) -> Option<(Real, Real)> {
+        let (d, f, s) = dist_poly_circ_with_face_and_supp(
+            self.nvert,
+            &self.vertx,
+            &self.verty,
+            circle.centrex,
+            circle.centrey,
+        );
+        let inside = poly_contains_pnt(
+            self.nvert,
+            &self.vertx,
+            &self.verty,
+            circle.centrex,
+            circle.centrey,
+        );
+        if !inside && d >= circle.radius {
+            return None;
+        }
+        if d == 0. {
+            // The centre is on edge f, so push straight out through it whatever the winding.
+            let n = self.outward_normal(f) * circle.radius;
+            return Some((n.x, n.y));
+        }
+        let dx = (circle.centrex - s.x) / d;
+        let dy = (circle.centrey - s.y) / d;
+        if inside {
+            Some((-dx * (circle.radius + d), -dy * (circle.radius + d)))
+        } else {
+            Some((dx * (circle.radius - d), dy * (circle.radius - d)))
+        }
+    }
+
+    fn sat_mtv<U>(&self, other: &Collidable<U>) -> Option<(Real, Real)> {
+        let mut axes = self.separating_axes();
+        axes.extend(other.separating_axes());
+        // Rounded shapes also need the axis between the closest points of the cores.
+        if self.rounding() > 0. || other.rounding() > 0. {
+            let (p, q) = self.closest_core_points(other);
+            axes.push((p.x - q.x, p.y - q.y));
+        }
+        let mut best = Real::MAX;
+        let mut mtv = (0., 0.);
+        for &(nx, ny) in &axes {
+            let n = (sqrf(nx) + sqrf(ny)).sqrt();
+            if n == 0. {
+                continue;
+            }
+            let (ax, ay) = (nx / n, ny / n);
+            let (mina, maxa) = self.project_onto_axis(ax, ay);
+            let (minb, maxb) = other.project_onto_axis(ax, ay);
+            // Pushing self along +axis clears other after maxb - mina, along -axis after
+            // maxa - minb.
+            let (push, sign) = if maxb - mina < maxa - minb {
+                (maxb - mina, 1.)
+            } else {
+                (maxa - minb, -1.)
+            };
+            if push <= 0. {
+                return None;
+            }
+            if push < best {
+                best = push;
+                mtv = (ax * push * sign, ay * push * sign);
+            }
+        }
+        Some(mtv)
+    }
+
+    fn separated_by_normals<U>(&self, other: &Collidable<U>) -> bool {
+        for i in 0..self.nvert {
+            let (ax, ay) = (self.normx[i], self.normy[i]);
+            // Zero-length edges have no normal and can't separate anything.
+            if ax == 0. && ay == 0. {
+                continue;
+            }
+            let (mina, maxa) = project_poly(self.nvert, &self.vertx, &self.verty, ax, ay);
+            let (minb, maxb) = project_poly(other.nvert, &other.vertx, &other.verty, ax, ay);
+            if maxa <= minb || maxb <= mina {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Real, b: Real) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn close2(a: (Real, Real), b: (Real, Real)) -> bool {
+        close(a.0, b.0) && close(a.1, b.1)
+    }
+
+    fn clockwise(mut c: Collidable) -> Collidable {
+        c.vertx.reverse();
+        c.verty.reverse();
+        c.update_normals();
+        c
+    }
+
+    #[test]
+    fn rotated_square_overlaps_axis_aligned_square() {
+        let a = Collidable::new_rect(0, 0, 0., 0., 2., 2.);
+        let mut b = Collidable::new_rect(0, 1, 2.2, 0., 2., 2.);
+        assert!(!a.collides(&b));
+        // Turned 45 degrees, b's left corner reaches back past x = 2.
+        b.rotate(consts::PI / 4.);
+        assert!(a.collides(&b));
+        assert!(b.collides(&a));
+    }
+
+    #[test]
+    fn polygons_sharing_an_edge_dont_collide() {
+        let a = Collidable::new_rect(0, 0, 0., 0., 2., 2.);
+        let b = Collidable::new_rect(0, 1, 2., 0., 2., 2.);
+        assert!(!a.collides(&b));
+        assert!(!b.collides(&a));
+    }
+
+    #[test]
+    fn circles_touching_at_a_point_dont_collide() {
+        let a = Collidable::new_circle(0, 0, 0., 0., 1.);
+        let b = Collidable::new_circle(0, 1, 3., 0., 2.);
+        assert!(!a.collides(&b));
+        let c = Collidable::new_circle(0, 2, 2.999, 0., 2.);
+        assert!(a.collides(&c));
+        assert!(c.collides(&a));
+    }
+
+    #[test]
+    fn mtv_for_overlapping_rects_takes_the_short_axis() {
+        let a = Collidable::new_rect(0, 0, 0., 0., 4., 4.);
+        let b = Collidable::new_rect(0, 1, 3.5, 1., 4., 4.);
+        let mtv = a.collision_mtv(&b).unwrap();
+        assert!(close2(mtv, (-0.5, 0.)), "{:?}", mtv);
+        assert!(close2(b.collision_mtv(&a).unwrap(), (0.5, 0.)));
+        let far = Collidable::new_rect(0, 2, 10., 0., 1., 1.);
+        assert_eq!(a.collision_mtv(&far), None);
+    }
+
+    #[test]
+    fn circle_centred_on_an_edge_is_pushed_out_for_either_winding() {
+        let ccw = Collidable::new_rect(0, 0, 0., 0., 2., 2.);
+        let cw = clockwise(ccw.clone());
+        let circle = Collidable::new_circle(0, 1, 2., 1., 0.5);
+        assert!(close2(circle.collision_mtv(&ccw).unwrap(), (0.5, 0.)));
+        assert!(close2(circle.collision_mtv(&cw).unwrap(), (0.5, 0.)));
+        assert!(close2(cw.collision_mtv(&circle).unwrap(), (-0.5, 0.)));
+    }
+
+    fn l_shape() -> Collidable {
+        Collidable::new_poly(
+            0,
+            0,
+            6,
+            vec![0., 2., 2., 1., 1., 0.],
+            vec![0., 0., 1., 1., 2., 2.],
+        )
+    }
+
+    #[test]
+    fn contains_point_in_a_concave_polygon() {
+        for poly in &[l_shape(), clockwise(l_shape())] {
+            assert!(poly.contains_point(0.5, 1.5));
+            assert!(poly.contains_point(1.5, 0.5));
+            // Inside the bounding box but in the notch.
+            assert!(!poly.contains_point(1.5, 1.5));
+            assert!(!poly.contains_point(3., 0.5));
+            // The boundary counts as inside.
+            assert!(poly.contains_point(1., 1.5));
+            assert!(poly.contains_point(2., 0.));
+        }
+    }
+
+    #[test]
+    fn contains_point_in_a_circle() {
+        let c = Collidable::new_circle(0, 0, 1., 1., 2.);
+        assert!(c.contains_point(2., 2.));
+        assert!(c.contains_point(3., 1.));
+        assert!(!c.contains_point(3., 3.));
+    }
+
+    #[test]
+    fn rotating_a_rect_a_quarter_turn() {
+        let mut r = Collidable::new_rect(0, 0, 0., 0., 4., 2.);
+        r.rotate(consts::PI / 2.);
+        // About the centre (2, 1): (0, 0) -> (3, -1), (4, 0) -> (3, 3), (4, 2) -> (1, 3).
+        let expected = [(3., -1.), (3., 3.), (1., 3.), (1., -1.)];
+        let start = (0..4).find(|&i| close2((r.vertx[i], r.verty[i]), expected[0]));
+        let start = start.expect("no vertex landed on (3, -1)");
+        for (k, &e) in expected.iter().enumerate() {
+            let i = (start + k) % 4;
+            assert!(
+                close2((r.vertx[i], r.verty[i]), e),
+                "{:?}",
+                (r.vertx[i], r.verty[i])
+            );
+        }
+        assert!(close(r.width, 2.) && close(r.height, 4.));
+        assert_eq!(r.winding(), Winding::CounterClockwise);
+    }
+
+    #[test]
+    fn rotate_around_an_explicit_pivot() {
+        let mut c = Collidable::new_circle(0, 0, 1., 0., 0.5);
+        c.rotate_around(0., 0., consts::PI / 2.);
+        assert!(close2((c.centrex, c.centrey), (0., 1.)));
+        c.rotate(1.);
+        assert!(close2((c.centrex, c.centrey), (0., 1.)));
+    }
+
+    #[test]
+    fn segment_t_junction_touches() {
+        let bar = Collidable::new_segment(0, 0, 0., 0., 2., 0.);
+        let stem = Collidable::new_segment(0, 1, 1., 0., 1., 1.);
+        assert!(bar.collides(&stem) && stem.collides(&bar));
+        assert_eq!(bar.collision_mtv(&stem), Some((0., 0.)));
+        let m = bar
+            .manifold(&stem)
+            .expect("touching segments have a manifold");
+        assert_eq!(m.depth, 0.);
+        assert!(close2(m.normal, (0., 1.)));
+        assert!(close2(m.contacts[0], (1., 0.)));
+        let m = stem
+            .manifold(&bar)
+            .expect("touching segments have a manifold");
+        assert!(close2(m.normal, (0., -1.)));
+    }
+
+    #[test]
+    fn collinear_overlapping_segments_collide() {
+        let a = Collidable::new_segment(0, 0, 0., 0., 2., 0.);
+        let b = Collidable::new_segment(0, 1, 1., 0., 3., 0.);
+        let c = Collidable::new_segment(0, 2, 2.5, 0., 4., 0.);
+        assert!(a.collides(&b) && b.collides(&a));
+        assert!(!a.collides(&c));
+        assert!(a.collision_mtv(&b).is_some());
+        assert!(a.collision_mtv(&c).is_none() && a.manifold(&c).is_none());
+    }
+
+    #[test]
+    fn segment_against_polygon_and_circle() {
+        let square = Collidable::new_rect(0, 0, 0., 0., 2., 2.);
+        let through = Collidable::new_segment(0, 1, -1., 1., 3., 1.);
+        let inside = Collidable::new_segment(0, 2, 0.5, 0.5, 1.5, 1.5);
+        let along_edge = Collidable::new_segment(0, 3, 0., 2., 2., 2.);
+        let clear = Collidable::new_segment(0, 4, 3., 0., 3., 2.);
+        assert!(square.collides(&through) && square.collides(&inside));
+        assert!(square.collides(&along_edge) && !square.collides(&clear));
+        assert!(through.collision_mtv(&square).is_some());
+        let circle = Collidable::new_circle(0, 5, 1., 1., 1.);
+        let tangent = Collidable::new_segment(0, 6, -1., 2., 3., 2.);
+        assert!(circle.collides(&through) && circle.collides(&tangent));
+        assert!(!circle.collides(&clear));
+    }
+
+    #[test]
+    fn ellipse_contains_its_axis_ends_and_nothing_past_them() {
+        let e = Collidable::new_ellipse(0, 0, 1., 2., 3., 1.);
+        for &(x, y) in &[(4., 2.), (-2., 2.), (1., 3.), (1., 1.), (1., 2.)] {
+            assert!(e.contains_point(x, y), "{:?}", (x, y));
+        }
+        for &(x, y) in &[(4.01, 2.), (-2.01, 2.), (1., 3.01), (1., 0.99), (3.9, 2.9)] {
+            assert!(!e.contains_point(x, y), "{:?}", (x, y));
+        }
+        // (x/3)^2 + (y/1)^2 = 1 at x = 3 cos(t), y = sin(t).
+        let t: Real = 0.7;
+        assert!(e.contains_point(1. + 3. * t.cos() * 0.999, 2. + t.sin() * 0.999));
+        assert!(!e.contains_point(1. + 3. * t.cos() * 1.001, 2. + t.sin() * 1.001));
+    }
+
+    #[test]
+    fn ellipse_against_circles_along_each_axis() {
+        let e = Collidable::new_ellipse(0, 0, 0., 0., 3., 1.);
+        assert!(e.collides(&Collidable::new_circle(0, 1, 3.9, 0., 1.)));
+        assert!(!e.collides(&Collidable::new_circle(0, 1, 4.1, 0., 1.)));
+        assert!(e.collides(&Collidable::new_circle(0, 1, 0., 1.9, 1.)));
+        assert!(!e.collides(&Collidable::new_circle(0, 1, 0., 2.1, 1.)));
+        assert!(e.collides(&Collidable::new_circle(0, 1, 0., 0., 0.5)));
+    }
+
+    fn edges_of_length(c: &Collidable, len: Real) -> Vec<((Real, Real), (Real, Real))> {
+        (0..c.nvert)
+            .filter(|&i| close(c.edge_length(i), len))
+            .map(|i| {
+                let j = (i + 1) % c.nvert;
+                ((c.vertx[i], c.verty[i]), (c.vertx[j], c.verty[j]))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rounded_rect_has_four_straight_edges() {
+        let r = Collidable::new_rounded_rect(0, 0, 0., 0., 10., 6., 1.);
+        // Five points per quarter arc.
+        assert_eq!(r.nvert, 20);
+        assert!(r.is_convex());
+        let long = edges_of_length(&r, 8.);
+        let short = edges_of_length(&r, 4.);
+        assert_eq!(long.len(), 2);
+        assert_eq!(short.len(), 2);
+        assert!(long.iter().any(|&(a, b)| close(a.1, 0.) && close(b.1, 0.)));
+        assert!(long.iter().any(|&(a, b)| close(a.1, 6.) && close(b.1, 6.)));
+        assert!(short
+            .iter()
+            .any(|&(a, b)| close(a.0, 10.) && close(b.0, 10.)));
+        assert!(short.iter().any(|&(a, b)| close(a.0, 0.) && close(b.0, 0.)));
+        let (minx, miny, maxx, maxy) = r.aabb();
+        assert!(close2((minx, miny), (0., 0.)) && close2((maxx, maxy), (10., 6.)));
+    }
+
+    #[test]
+    fn rounded_rect_radius_is_clamped() {
+        let r = Collidable::new_rounded_rect(0, 0, 0., 0., 10., 6., 5.);
+        // The sides collapse to nothing, so their shared corner points are merged.
+        assert_eq!(r.nvert, 18);
+        assert_eq!(edges_of_length(&r, 4.).len(), 2);
+        assert!((0..r.nvert).all(|i| r.edge_length(i) > 0.));
+        assert!(r.contains_point(5., 0.) && !r.contains_point(0.2, 0.2));
+    }
+
+    /// What a serde round trip keeps: the serialized fields, rebuilt through
+    /// `CollidableRepr` as `Deserialize` does. The crate has no format dependency to
+    /// encode with, so this stands in for writing and reading the bytes.
+    #[cfg(feature = "serde")]
+    fn reload(c: &Collidable) -> Collidable {
+        Collidable::from(CollidableRepr {
+            collidable_type: c.collidable_type,
+            collidable_shape: c.collidable_shape,
+            collidable_id: c.collidable_id,
+            centrex: c.centrex,
+            centrey: c.centrey,
+            radius: c.radius,
+            width: c.width,
+            height: c.height,
+            nvert: c.nvert,
+            vertx: c.vertx.clone(),
+            verty: c.verty.clone(),
+            sector: c.sector,
+            margin: c.margin,
+            angle: c.angle,
+            data: (),
+        })
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_recomputes_derived_fields() {
+        let shapes = vec![
+            Collidable::new_circle(1, 0, 3., -2., 1.5),
+            Collidable::new_rect(2, 1, -1., 4., 3., 2.),
+            l_shape(),
+            clockwise(l_shape()),
+        ];
+        for c in &shapes {
+            let back = reload(c);
+            assert_eq!(&back, c);
+            assert_eq!(back.aabb(), c.aabb());
+            assert_eq!((&back.normx, &back.normy), (&c.normx, &c.normy));
+        }
+    }
+
+    #[cfg(feature = "f64")]
+    #[test]
+    fn f64_keeps_precision_far_from_the_origin() {
+        // f32 spaces values near 1e7 a whole unit apart, so these gaps would vanish.
+        let x = 1e7;
+        let a = Collidable::new_rect(0, 0, x, x, 0.25, 0.25);
+        let b = Collidable::new_rect(0, 1, x + 0.3, x, 0.25, 0.25);
+        let c = Collidable::new_rect(0, 2, x + 0.125, x + 0.125, 0.25, 0.25);
+        assert!(!a.collides(&b));
+        assert!(a.collides(&c));
+        assert!((a.distance(&b) - 0.05).abs() < 1e-6);
+        let (mx, my) = a.collision_mtv(&c).expect("overlapping rects have an MTV");
+        assert!((mx.abs() + my.abs() - 0.125).abs() < 1e-6);
+        let circle = Collidable::new_circle(0, 3, x + 0.5, x + 0.5, 0.1);
+        assert!(circle.contains_point(x + 0.55, x + 0.5));
+        assert!(!circle.contains_point(x + 0.65, x + 0.5));
+    }
+
+    #[test]
+    fn ray_grazing_a_circle_hits_tangentially() {
+        let c = Collidable::new_circle(0, 0, 0., 0., 1.);
+        let hit = c
+            .raycast(-5., 1., 1., 0.)
+            .expect("a tangent ray counts as a hit");
+        assert!(close(hit.distance, 5.));
+        assert!(close2(hit.point, (0., 1.)));
+        assert!(close2(hit.normal, (0., 1.)));
+        assert!(c.raycast(-5., 1.001, 1., 0.).is_none());
+        let hit = c
+            .raycast(-5., 0.999, 2., 0.)
+            .expect("just inside the tangent still hits");
+        assert!(hit.distance > 4.9 && hit.distance < 5.);
+    }
+
+    #[test]
+    fn raycast_hits_polygon_edges_and_starts_inside() {
+        let r = Collidable::new_rect(0, 0, 0., 0., 2., 2.);
+        let hit = r.raycast(-3., 1., 1., 0.).expect("ray aimed at the rect");
+        assert!(close(hit.distance, 3.) && close2(hit.point, (0., 1.)));
+        assert!(close2(hit.normal, (-1., 0.)));
+        assert!(r.raycast(-3., 1., -1., 0.).is_none());
+        let inside = r
+            .raycast(1., 1., 0., 3.)
+            .expect("rays from inside hit at once");
+        assert_eq!(inside.distance, 0.);
+        assert!(close2(inside.point, (1., 1.)) && close2(inside.normal, (0., -1.)));
+        assert!(r.raycast(-3., 1., 0., 0.).is_none());
+    }
+
+    #[test]
+    fn sweep_catches_a_bullet_tunnelling_through_a_thin_wall() {
+        let wall = Collidable::new_rect(0, 0, 5., -10., 0.1, 20.);
+        let (r, vx) = (0.2, 20.);
+        // Before and after the step the bullet is clear of the wall on either side.
+        assert!(!Collidable::new_circle(0, 1, 0., 0., r).collides(&wall));
+        assert!(!Collidable::new_circle(0, 1, vx, 0., r).collides(&wall));
+        let t = Collidable::sweep_circle(0., 0., r, vx, 0., &wall).expect("the sweep hits");
+        assert!(close(t, (5. - r) / vx));
+        assert!(Collidable::sweep_circle(0., 0., r, -vx, 0., &wall).is_none());
+        assert!(Collidable::sweep_circle(0., 0., r, 4., 0., &wall).is_none());
+        assert!(Collidable::sweep_circle(0., 11., r, vx, 0., &wall).is_none());
+    }
+
+    #[test]
+    fn sweep_starting_in_contact_returns_zero() {
+        let wall = Collidable::new_rect(0, 0, 0., 0., 1., 1.);
+        assert_eq!(
+            Collidable::sweep_circle(0.5, 1.1, 0.2, 0., 5., &wall),
+            Some(0.)
+        );
+        let target = Collidable::new_circle(0, 1, 10., 0., 1.);
+        let t = Collidable::sweep_circle(0., 0., 1., 20., 0., &target).expect("hits the circle");
+        assert!(close(t, 0.4));
+    }
+
+    #[test]
+    fn arcs_wider_than_half_a_circle() {
+        // Three quarters of a circle, open towards -x.
+        let arc = Collidable::new_arc(0, 0, 0., 0., 2., 1., 0., consts::PI * 1.5);
+        assert!(!arc.is_convex());
+        // Points and circles are tested against the fan itself, notch included.
+        assert!(arc.contains_point(1., 0.) && arc.contains_point(0., 1.5));
+        assert!(!arc.contains_point(-1., 0.));
+        assert!(arc.collides(&Collidable::new_circle(0, 1, 0., 1.5, 0.2)));
+        assert!(!arc.collides(&Collidable::new_circle(0, 1, -1., 0., 0.2)));
+        // Other polygons collide with the convex hull, so a box in the notch is reported.
+        let in_notch = Collidable::new_rect(0, 2, -1.2, -0.2, 0.4, 0.4);
+        assert!(arc.collides(&in_notch));
+        assert!(!arc.collides(&Collidable::new_rect(0, 3, -2.5, -0.2, 0.4, 0.4)));
+        // Splitting it into convex pieces recovers the notch.
+        let pieces = arc.decompose_convex();
+        assert!(pieces.len() > 1);
+        assert!(!pieces.iter().any(|p| p.collides(&in_notch)));
+    }
+
+    #[test]
+    fn points_just_past_the_wedge_are_outside_the_arc() {
+        let at = |a: Real, r: Real| (r * a.cos(), r * a.sin());
+        for &rad in &[consts::PI / 2., consts::PI * 1.5] {
+            let arc = Collidable::new_arc(0, 0, 0., 0., 2., 1., 0., rad);
+            for &side in &[1., -1.] {
+                let edge = side * rad / 2.;
+                let (x, y) = at(edge + side * 0.05, 1.5);
+                assert!(!arc.arc_contains_point(x, y) && !arc.contains_point(x, y));
+                let (x, y) = at(edge - side * 0.05, 1.5);
+                assert!(arc.arc_contains_point(x, y) && arc.contains_point(x, y));
+            }
+            // Inside the wedge but past the radius is out too.
+            assert!(!arc.arc_contains_point(2.05, 0.));
+        }
+    }
+
+    #[test]
+    fn separating_axes_are_the_edge_normals() {
+        let r = Collidable::new_rect(0, 0, 0., 0., 2., 1.);
+        let axes = r.separating_axes();
+        assert_eq!(axes.len(), 4);
+        for (i, &(x, y)) in axes.iter().enumerate() {
+            assert_eq!((x, y), (r.normx[i], r.normy[i]));
+            let (ex, ey) = (
+                r.vertx[(i + 1) % 4] - r.vertx[i],
+                r.verty[(i + 1) % 4] - r.verty[i],
+            );
+            assert!(close(x * ex + y * ey, 0.));
+        }
+        assert!(Collidable::new_circle(0, 1, 0., 0., 1.)
+            .separating_axes()
+            .is_empty());
+        assert!(Collidable::new_ellipse(0, 1, 0., 0., 2., 1.)
+            .separating_axes()
+            .is_empty());
+        let repeated = Collidable::new_poly(0, 2, 4, vec![0., 1., 1., 0.], vec![0., 0., 0., 1.]);
+        assert_eq!(repeated.separating_axes().len(), 3);
+    }
+
+    #[test]
+    fn separating_axes_drive_a_hand_rolled_sat_test() {
+        let separated = |a: &Collidable, b: &Collidable| {
+            a.separating_axes()
+                .iter()
+                .chain(&b.separating_axes())
+                .any(|&(x, y)| {
+                    let ((mina, maxa), (minb, maxb)) =
+                        (a.project_onto_axis(x, y), b.project_onto_axis(x, y));
+                    maxa <= minb || maxb <= mina
+                })
+        };
+        let a = Collidable::new_rect(0, 0, 0., 0., 2., 2.);
+        let mut b = Collidable::new_rect(0, 1, 2.5, 0.5, 1., 1.);
+        assert!(separated(&a, &b) && !a.collides(&b));
+        b.rotate(consts::PI / 4.);
+        b.translate(-1., 0.);
+        assert!(!separated(&a, &b) && a.collides(&b));
+    }
+
+    #[test]
+    fn epa_agrees_with_collision_mtv_for_polygons() {
+        let a = Collidable::new_rect(0, 0, 0., 0., 4., 2.);
+        let mut tilted = Collidable::new_rect(0, 1, 2.5, 1.2, 2., 2.);
+        tilted.rotate(0.3);
+        let tri = Collidable::new_poly(0, 2, 3, vec![1., 3., 2.], vec![-1., -1., 0.5]);
+        let hex = Collidable::new_regular(0, 3, 4., 1., 1.2, 6, 0.1);
+        for b in &[
+            Collidable::new_rect(0, 4, 3., 0.5, 2., 1.),
+            tilted,
+            tri,
+            hex,
+        ] {
+            let mtv = a.collision_mtv(b).expect("the shapes overlap");
+            let epa = a.epa_penetration(b).expect("EPA agrees they overlap");
+            assert!(close2(mtv, epa), "{:?} vs {:?}", mtv, epa);
+            let mut moved = a.clone();
+            moved.translate(epa.0 * 1.001, epa.1 * 1.001);
+            assert!(!moved.collides(b));
+        }
+    }
+
+    #[test]
+    fn epa_on_touching_or_separate_shapes() {
+        let a = Collidable::new_rect(0, 0, 0., 0., 1., 1.);
+        assert!(a
+            .epa_penetration(&Collidable::new_rect(0, 1, 1., 0., 1., 1.))
+            .is_none());
+        assert!(a
+            .epa_penetration(&Collidable::new_rect(0, 1, 1., 1., 1., 1.))
+            .is_none());
+        assert!(a
+            .epa_penetration(&Collidable::new_rect(0, 1, 3., 0., 1., 1.))
+            .is_none());
+    }
+
+    #[test]
+    fn box_resting_on_a_box_has_two_contacts() {
+        let floor = Collidable::new_rect(0, 0, 0., 0., 10., 2.);
+        // Sunk 0.1 into the floor, well inside its top face.
+        let crate_ = Collidable::new_rect(0, 1, 3., 1.9, 2., 2.);
+        let m = crate_.manifold(&floor).expect("the boxes overlap");
+        assert!(close2(m.normal, (0., -1.)));
+        assert!(close(m.depth, 0.1));
+        let mut contacts = m.contacts.clone();
+        contacts.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        assert_eq!(contacts.len(), 2);
+        // The faces are parallel, so the first shape's face is the reference and the contacts
+        // are the other's edge clipped to the crate's width.
+        assert!(close2(contacts[0], (3., 2.)) && close2(contacts[1], (5., 2.)));
+        let m = floor.manifold(&crate_).expect("the boxes overlap");
+        assert!(close2(m.normal, (0., 1.)) && close(m.depth, 0.1));
+        let mut contacts = m.contacts.clone();
+        contacts.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        assert!(close2(contacts[0], (3., 1.9)) && close2(contacts[1], (5., 1.9)));
+    }
+
+    #[test]
+    fn circle_manifold_has_one_contact_on_its_surface() {
+        let floor = Collidable::new_rect(0, 0, 0., 0., 10., 2.);
+        let ball = Collidable::new_circle(0, 1, 4., 2.8, 1.);
+        let m = ball.manifold(&floor).expect("the ball overlaps the floor");
+        assert!(close2(m.normal, (0., -1.)) && close(m.depth, 0.2));
+        assert_eq!(m.contacts.len(), 1);
+        assert!(close2(m.contacts[0], (4., 1.8)));
+        assert!(ball
+            .manifold(&Collidable::new_circle(0, 2, 7., 2.8, 1.))
+            .is_none());
+    }
+
+    #[test]
+    fn approx_eq_tolerates_float_error_but_not_different_geometry() {
+        let a = Collidable::new_rect(0, 0, 0., 0., 2., 1.);
+        let mut b = Collidable::new_rect(3, 7, 0., 0., 2., 1.);
+        assert_ne!(a, b);
+        assert!(a.approx_eq(&b, 0.));
+        for _ in 0..4 {
+            b.rotate(consts::PI / 2.);
+        }
+        assert!(a.approx_eq(&b, 1e-4));
+        b.translate(1e-3, 0.);
+        assert!(!a.approx_eq(&b, 1e-4) && a.approx_eq(&b, 1e-2));
+        assert!(!a.approx_eq(&clockwise(a.clone()), 1e-4));
+        let circle = Collidable::new_circle(0, 0, 1., 0.5, 0.5);
+        assert!(!a.approx_eq(&circle, 10.));
+        assert!(circle.approx_eq(&Collidable::new_circle(0, 1, 1., 0.5, 0.5), 0.));
+        assert!(!circle.approx_eq(&Collidable::new_circle(0, 1, 1., 0.5, 0.6), 0.05));
+    }
+
+    #[test]
+    fn flipped_triangle_keeps_outward_normals() {
+        let tri = Collidable::new_poly(0, 0, 3, vec![0., 3., 1.], vec![0., 0., 2.]);
+        for &flip_x in &[true, false] {
+            let mut t = tri.clone();
+            if flip_x {
+                t.flip_x(5.);
+            } else {
+                t.flip_y(-1.);
+            }
+            assert_eq!(t.winding(), Winding::CounterClockwise);
+            let (cx, cy) = t.centroid();
+            for i in 0..3 {
+                let (mx, my) = (
+                    (t.vertx[i] + t.vertx[(i + 1) % 3]) * 0.5,
+                    (t.verty[i] + t.verty[(i + 1) % 3]) * 0.5,
+                );
+                assert!(t.normx[i] * (mx - cx) + t.normy[i] * (my - cy) > 0.);
+            }
+            let mut back = t.clone();
+            if flip_x {
+                back.flip_x(5.);
+            } else {
+                back.flip_y(-1.);
+            }
+            let inside = if flip_x { (9., 0.5) } else { (1., -2.5) };
+            assert!(t.contains_point(inside.0, inside.1));
+            assert!(t.collides(&Collidable::new_circle(0, 1, inside.0, inside.1, 0.1)));
+            assert!((0..3).any(|k| back.vertx[k] == tri.vertx[0] && back.verty[k] == tri.verty[0]));
+        }
+    }
+
+    #[test]
+    fn flipped_arc_keeps_its_boundary_length() {
+        let arc = Collidable::new_arc(0, 0, 1., 1., 2., 1., 0., consts::PI / 2.);
+        let curve = arc.boundary_length();
+        assert!(curve > 2.9 && curve < consts::PI);
+        assert!(close(arc.perimeter() - curve, 4.));
+        for axis in &[0., 3.] {
+            let mut flipped = arc.clone();
+            flipped.flip_x(*axis);
+            assert!(close(flipped.boundary_length(), curve));
+            flipped.flip_y(*axis);
+            assert!(close(flipped.boundary_length(), curve));
+        }
+    }
+
+    #[test]
+    fn batch_normals_match_individual_updates() {
+        let mut items = vec![
+            Collidable::new_rect(0, 0, 0., 0., 2., 1.),
+            Collidable::new_circle(0, 1, 5., 5., 1.),
+            l_shape(),
+            Collidable::new_capsule(0, 3, 0., 0., 3., 1., 0.5),
+            Collidable::new_ellipse(0, 4, 0., 0., 2., 1.),
+        ];
+        for c in &mut items {
+            c.vertx.iter_mut().for_each(|x| *x *= 2.);
+        }
+        let mut expected = items.clone();
+        for c in &mut expected {
+            c.update_normals();
+        }
+        let mut serial = items.clone();
+        update_normals_batch(&mut serial);
+        assert_eq!(serial, expected);
+        for &threads in &[0, 1, 2, 16] {
+            let mut parallel = items.clone();
+            update_normals_batch_parallel(&mut parallel, threads);
+            assert_eq!(parallel, expected);
+        }
+    }
+
+    #[test]
+    fn bowtie_is_not_simple_but_quads_are() {
+        let bowtie = Collidable::new_poly(0, 0, 4, vec![0., 2., 0., 3.], vec![0., 2., 2., 0.]);
+        assert!(!bowtie.is_simple());
+        let quad = Collidable::new_poly(0, 1, 4, vec![0., 3., 2., 0.], vec![0., 0., 2., 2.]);
+        assert!(quad.is_simple());
+        assert!(clockwise(quad).is_simple());
+        assert!(l_shape().is_simple());
+        assert!(Collidable::new_poly(0, 2, 3, vec![0., 1., 0.], vec![0., 0., 1.]).is_simple());
+        assert!(Collidable::new_circle(0, 3, 0., 0., 1.).is_simple());
+    }
+
+    #[test]
+    fn minkowski_difference_contains_the_origin_iff_overlapping() {
+        let a = Collidable::new_rect(0, 0, 0., 0., 2., 2.);
+        let overlapping = Collidable::new_rect(0, 1, 1., 1., 2., 2.);
+        let disjoint = Collidable::new_rect(0, 2, 3., 0.5, 2., 2.);
+        let d = a.minkowski_difference(&overlapping);
+        assert!(d.is_convex() && d.contains_point(0., 0.));
+        // A - B spans [-3, 1] on both axes.
+        let (minx, miny, maxx, maxy) = d.aabb();
+        assert!(close2((minx, miny), (-3., -3.)) && close2((maxx, maxy), (1., 1.)));
+        assert!(close(d.area(), 16.));
+        assert!(!a.minkowski_difference(&disjoint).contains_point(0., 0.));
+        let mut tilted = Collidable::new_regular(0, 3, 2.5, 1., 1., 3, 0.4);
+        assert_eq!(
+            a.minkowski_difference(&tilted).contains_point(0., 0.),
+            a.collides(&tilted)
+        );
+        tilted.translate(2., 0.);
+        assert_eq!(
+            a.minkowski_difference(&tilted).contains_point(0., 0.),
+            a.collides(&tilted)
+        );
+    }
+
+    #[test]
+    fn ball_bounces_off_a_floor() {
+        let floor = Collidable::new_rect(0, 0, -10., -1., 20., 1.);
+        assert!(close2(floor.surface_normal_at(2., 0.), (0., 1.)));
+        assert!(close2(floor.surface_normal_at(-10.2, -0.5), (-1., 0.)));
+        assert!(close2(floor.reflect_velocity(3., -2., 2., 0.), (3., 2.)));
+        let ball = Collidable::new_circle(0, 1, 0., 0., 1.);
+        assert!(close2(ball.surface_normal_at(0., -1.), (0., -1.)));
+        assert!(close2(ball.reflect_velocity(1., 5., 0., 1.), (1., -5.)));
+    }
+
+    #[test]
+    fn convex_pieces_keep_margin_and_angle() {
+        let mut l = l_shape().with_data("wall");
+        l.margin = 0.25;
+        l.angle = 0.5;
+        let pieces = l.decompose_convex();
+        assert!(pieces.len() > 1);
+        for p in &pieces {
+            assert!(p.is_convex());
+            assert_eq!((p.margin, p.angle, p.data), (0.25, 0.5, "wall"));
+        }
+        // 0.1 clear of the right edge, so only the margin makes it collide.
+        let near = Collidable::new_circle(0, 1, 2.2, 0.5, 0.1);
+        assert!(l.collides(&near));
+        assert_eq!(pieces.iter().any(|p| p.collides(&near)), l.collides(&near));
+    }
+
+    #[test]
+    fn capsule_spine_crossing_an_edge_is_pushed_clear() {
+        let capsule = Collidable::new_capsule(
+            0,
+            0,
+            -1.1435664,
+            1.2659297,
+            -1.8024749,
+            -0.19969869,
+            0.11815182,
+        );
+        let poly = Collidable::from_points(
+            0,
+            1,
+            &[
+                (-3.2272668, 1.7978101),
+                (-2.4362495, 0.71872246),
+                (-1.633342, -0.09891796),
+                (-1.4392948, 3.046266),
+            ],
+        );
+        assert!(capsule.collides(&poly));
+        for &(a, b, flip) in &[(&capsule, &poly, false), (&poly, &capsule, true)] {
+            let (mx, my) = a.collision_mtv(b).expect("the shapes overlap");
+            let epa = a.epa_penetration(b).expect("EPA agrees they overlap");
+            assert!((mx.hypot(my) - epa.0.hypot(epa.1)).abs() < 1e-3);
+            let (mut a, mut b) = (a.clone(), b.clone());
+            if flip {
+                b.translate(-mx * 1.001, -my * 1.001);
+            } else {
+                a.translate(mx * 1.001, my * 1.001);
+            }
+            assert!(!a.collides(&b));
+        }
+    }
+
+    #[test]
+    fn capsule_resting_on_a_corner_is_pushed_from_the_vertex() {
+        let square = Collidable::new_rect(0, 0, 0., 0., 2., 2.);
+        let capsule = Collidable::new_capsule(0, 1, 2.3, 2.3, 4., 4., 0.5);
+        let (mx, my) = capsule
+            .collision_mtv(&square)
+            .expect("the cap overlaps the corner");
+        let depth = 0.5 - (2. * 0.09 as Real).sqrt();
+        assert!(close2(
+            (mx, my),
+            (depth * consts::FRAC_1_SQRT_2, depth * consts::FRAC_1_SQRT_2)
+        ));
+    }
+
+    #[test]
+    fn each_invariant_violation_has_its_own_error() {
+        let rect = Collidable::new_rect(0, 0, 0., 0., 2., 1.);
+        assert_eq!(rect.validate(), Ok(()));
+        assert_eq!(Collidable::new_circle(0, 0, 0., 0., 1.).validate(), Ok(()));
+        let broken = |f: &dyn Fn(&mut Collidable)| {
+            let mut c = rect.clone();
+            f(&mut c);
+            c.validate().unwrap_err()
+        };
+        let errors = vec![
+            broken(&|c| {
+                c.nvert = 2;
+                for v in &mut [&mut c.vertx, &mut c.verty, &mut c.normx, &mut c.normy] {
+                    v.truncate(2);
+                }
+            }),
+            broken(&|c| {
+                c.vertx.pop();
+            }),
+            broken(&|c| {
+                c.normy.pop();
+            }),
+            broken(&|c| c.vertx[1] = Real::NAN),
+            broken(&|c| c.margin = -1.),
+            broken(&|c| c.vertx[1] += 0.5),
+            broken(&|c| {
+                c.vertx.swap(1, 2);
+                c.verty.swap(1, 2);
+                c.update_normals();
+            }),
+        ];
+        assert_eq!(
+            errors,
+            vec![
+                CollidableError::TooFewVertices,
+                CollidableError::MismatchedLengths,
+                CollidableError::MismatchedNormals,
+                CollidableError::NonFiniteCoordinate,
+                CollidableError::NegativeSize,
+                CollidableError::StaleNormals,
+                CollidableError::SelfIntersecting,
+            ]
+        );
+        let mut circle = Collidable::new_circle(0, 0, 0., 0., 1.);
+        circle.radius = -1.;
+        assert_eq!(circle.validate(), Err(CollidableError::NegativeSize));
+    }
+
+    #[test]
+    fn constructors_accept_what_validate_rejects() {
+        // Bowties and negative radii still build; `validate` is what reports them.
+        let bowtie = Collidable::new_poly(0, 0, 4, vec![0., 2., 0., 3.], vec![0., 2., 2., 0.]);
+        assert_eq!(bowtie.validate(), Err(CollidableError::SelfIntersecting));
+        let dot = Collidable::new_circle(0, 0, 0., 0., -0.5);
+        assert_eq!(dot.validate(), Err(CollidableError::NegativeSize));
+        let line = Collidable::try_new_poly(0, 0, 3, vec![0., 1., 2.], vec![0., 1., 2.]);
+        assert_eq!(line.unwrap_err(), CollidableError::ZeroArea);
+        let builder = CollidableBuilder::new().build();
+        assert_eq!(builder.unwrap_err(), CollidableError::MissingShape);
+    }
+
+    /// xorshift64, so the property tests are random but the same on every run.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> Real {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 >> 40) as Real / (1u64 << 24) as Real
+        }
+
+        fn range(&mut self, lo: Real, hi: Real) -> Real {
+            lo + (hi - lo) * self.next()
+        }
+    }
+
+    /// A random convex polygon, circle, capsule or rotated rect within a few units of the
+    /// origin.
+    fn random_shape(rng: &mut Rng) -> Collidable {
+        let (x, y) = (rng.range(-2., 2.), rng.range(-2., 2.));
+        let size = rng.range(0.3, 2.);
+        let mut shape = match (rng.next() * 4.) as u32 {
+            0 => Collidable::new_circle(0, 0, x, y, size * 0.5),
+            1 => Collidable::new_capsule(
+                0,
+                0,
+                x,
+                y,
+                x + rng.range(-size, size),
+                y + rng.range(-size, size),
+                rng.range(0.1, 0.6),
+            ),
+            2 => Collidable::new_rect(0, 0, x, y, size, rng.range(0.3, 2.)),
+            _ => {
+                let n = 3 + (rng.next() * 6.) as usize;
+                let points: Vec<(Real, Real)> = (0..n)
+                    .map(|_| (x + rng.range(-size, size), y + rng.range(-size, size)))
+                    .collect();
+                Collidable::from_points(0, 0, &points)
+            }
+        };
+        shape.rotate(rng.range(0., 2. * consts::PI));
+        shape
+    }
+
+    /// Brute force overlap: whether any point sampled every `step` along the outline of `a`
+    /// lies in `b`. Convex shapes that overlap always have such a point, on one or the other.
+    fn boundary_inside(a: &Collidable, b: &Collidable, step: Real) -> bool {
+        a.outline().iter().any(|&((x1, y1), (x2, y2))| {
+            let n = ((x2 - x1).hypot(y2 - y1) / step).ceil().max(1.) as usize;
+            (0..=n).any(|k| {
+                let t = k as Real / n as Real;
+                b.contains_point(x1 + (x2 - x1) * t, y1 + (y2 - y1) * t)
+            })
+        })
+    }
+
+    #[test]
+    fn random_shapes_collide_consistently() {
+        let mut rng = Rng(0x9e3779b97f4a7c15);
+        let mut hits = 0;
+        for _ in 0..2000 {
+            let a = random_shape(&mut rng);
+            let b = random_shape(&mut rng);
+            assert!(a.collides(&a), "{:?}", a);
+            let collides = a.collides(&b);
+            assert_eq!(collides, b.collides(&a), "{:?}\n{:?}", a, b);
+            let gap = a.distance(&b);
+            // Within float error of touching either answer is fine.
+            if gap.abs() > 1e-3 {
+                assert_eq!(a.gjk_intersects(&b), collides, "{:?}\n{:?}", a, b);
+            }
+            // Sampling can miss a shallow overlap, but never invents one.
+            let step = 0.01;
+            let sampled = boundary_inside(&a, &b, step) || boundary_inside(&b, &a, step);
+            if sampled {
+                assert!(collides || gap.abs() < 1e-3, "{:?}\n{:?}", a, b);
+            }
+            if !collides {
+                continue;
+            }
+            hits += 1;
+            let (mx, my) = a.collision_mtv(&b).expect("colliding shapes have an MTV");
+            let depth = mx.hypot(my);
+            if depth > 5. * step {
+                assert!(sampled, "{:?}\n{:?}", a, b);
+            }
+            if a.collidable_shape == CollidableShape::Polygon
+                && b.collidable_shape == CollidableShape::Polygon
+            {
+                let (ex, ey) = a.epa_penetration(&b).expect("EPA agrees they overlap");
+                assert!((ex.hypot(ey) - depth).abs() < 1e-3, "{:?}\n{:?}", a, b);
+            }
+            // Pushing by the MTV, plus a little for rounding, must separate the shapes.
+            let mut moved = a.clone();
+            moved.translate(
+                mx * 1.001 + mx.signum() * 1e-4,
+                my * 1.001 + my.signum() * 1e-4,
+            );
+            assert!(
+                !moved.collides(&b),
+                "MTV {:?} leaves\n{:?}\n{:?}",
+                (mx, my),
+                a,
+                b
+            );
+        }
+        assert!(hits > 50, "only {} colliding pairs", hits);
+    }
+
+    #[test]
+    fn small_circle_inside_a_large_rect_collides() {
+        let rect = Collidable::new_rect(0, 0, 0., 0., 100., 50.);
+        let inside = Collidable::new_circle(0, 1, 40., 20., 1.);
+        assert!(rect.collides(&inside) && inside.collides(&rect));
+        assert!(clockwise(rect.clone()).collides(&inside));
+        assert!(rect.collides(&Collidable::new_circle(0, 2, 100.5, 20., 1.)));
+        assert!(!rect.collides(&Collidable::new_circle(0, 3, 102., 20., 1.)));
+        // Off a corner the nearest point is the vertex, not the edge lines.
+        assert!(!rect.collides(&Collidable::new_circle(0, 4, 100.8, 50.8, 1.)));
+    }
+
+    #[test]
+    fn translated_rect_collides_exactly_when_it_overlaps() {
+        let wall = Collidable::new_rect(0, 0, 10., 0., 2., 10.);
+        let mut mover = Collidable::new_rect(0, 1, 0., 4., 2., 2.);
+        let normals = mover.normx.clone();
+        // Step right in quarter units: the rect spans [x, x + 2], so it overlaps the wall
+        // strictly while 8 < x < 12.
+        for step in 0..64 {
+            let x = step as Real * 0.25;
+            assert_eq!(mover.collides(&wall), x > 8. && x < 12., "x = {}", x);
+            assert_eq!(mover.aabb(), (x, 4., x + 2., 6.));
+            mover.translate(0.25, 0.);
+        }
+        assert_eq!(mover.normx, normals);
+        mover.translate(-15., 10.);
+        assert!(close2((mover.centrex, mover.centrey), (2., 15.)));
+        assert!(!mover.collides(&wall));
+    }
+
+    #[test]
+    fn scaling_a_rect_by_two_one_doubles_only_its_width() {
+        let mut r = Collidable::new_rect(0, 0, 0., 0., 4., 2.);
+        r.scale(2., 1.);
+        assert!(close(r.width, 8.) && close(r.height, 2.));
+        assert!(close2((r.centrex, r.centrey), (2., 1.)));
+        let (minx, miny, maxx, maxy) = r.aabb();
+        assert!(close2((minx, miny), (-2., 0.)) && close2((maxx, maxy), (6., 2.)));
+        assert!(r.validate().is_ok());
+
+        // Circles grow by the larger factor.
+        let mut c = Collidable::new_circle(0, 1, 0., 0., 1.);
+        c.scale(2., 1.);
+        assert!(close(c.radius, 2.));
+    }
+
+    #[test]
+    fn parallel_capsules_overlapping_and_separated() {
+        let a = Collidable::new_capsule(0, 0, 0., 0., 10., 0., 1.);
+        // Side by side: the spines are 1.5 apart against radii summing to 2.
+        let beside = Collidable::new_capsule(0, 1, 3., 1.5, 7., 1.5, 1.);
+        assert!(a.collides(&beside) && beside.collides(&a));
+        assert!(close(a.distance(&beside), 0.));
+        let clear = Collidable::new_capsule(0, 2, 3., 2.5, 7., 2.5, 1.);
+        assert!(!a.collides(&clear) && !clear.collides(&a));
+        assert!(close(a.distance(&clear), 0.5));
+
+        // Collinear spines: only the end caps can meet.
+        let ahead = Collidable::new_capsule(0, 3, 11.5, 0., 20., 0., 1.);
+        assert!(a.collides(&ahead));
+        let far = Collidable::new_capsule(0, 4, 12.5, 0., 20., 0., 1.);
+        assert!(!a.collides(&far));
+        assert!(close(a.distance(&far), 0.5));
+
+        // Parallel but staggered so only the caps are near: the closest points are the
+        // ends, diagonal from each other.
+        let staggered = Collidable::new_capsule(0, 5, 11., 1.5, 20., 1.5, 1.);
+        assert!(a.collides(&staggered));
+        let staggered = Collidable::new_capsule(0, 6, 12., 1.5, 20., 1.5, 1.);
+        assert!(!a.collides(&staggered));
+
+        // Antiparallel endpoints give the same answer.
+        let reversed = Collidable::new_capsule(0, 7, 7., 1.5, 3., 1.5, 1.);
+        assert!(a.collides(&reversed));
+        let mtv = a.collision_mtv(&reversed).unwrap();
+        assert!(close2(mtv, (0., -0.5)));
+    }
+
+    #[test]
+    fn regular_four_gon_turned_an_eighth_is_a_square() {
+        let half = 1. / consts::SQRT_2;
+        let diamond = Collidable::new_regular(0, 0, 5., 5., 1., 4, consts::PI / 4.);
+        let square = Collidable::new_rect(0, 0, 5. - half, 5. - half, 2. * half, 2. * half);
+        assert_eq!(diamond.nvert, 4);
+        // The first vertex sits at π/4, the rect's top-right corner.
+        for k in 0..4 {
+            let j = (k + 2) % 4;
+            assert!(close2(
+                (diamond.vertx[k], diamond.verty[k]),
+                (square.vertx[j], square.verty[j])
+            ));
+            assert!(close2(
+                (diamond.normx[k], diamond.normy[k]),
+                (square.normx[j], square.normy[j])
+            ));
+        }
+        assert!(close(diamond.width, square.width) && close(diamond.height, square.height));
+        assert!(close2(
+            (diamond.centrex, diamond.centrey),
+            (square.centrex, square.centrey)
+        ));
+        assert!(close(diamond.area(), 2.));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 3 sides")]
+    fn regular_polygon_needs_three_sides() {
+        Collidable::new_regular(0, 0, 0., 0., 1., 2, 0.);
+    }
+
+    #[test]
+    fn nearest_point_from_inside_a_rect_is_on_the_nearest_wall() {
+        let r = Collidable::new_rect(0, 0, 0., 0., 10., 4.);
+        assert!(close2(r.nearest_point(1., 2.), (0., 2.)));
+        assert!(close2(r.nearest_point(9.5, 2.), (10., 2.)));
+        assert!(close2(r.nearest_point(5., 0.5), (5., 0.)));
+        assert!(close2(r.nearest_point(5., 3.), (5., 4.)));
+        assert!(close2(clockwise(r.clone()).nearest_point(5., 3.), (5., 4.)));
+        // Outside, it clamps to the boundary as usual.
+        assert!(close2(r.nearest_point(12., 6.), (10., 4.)));
+        assert!(close2(r.nearest_point(5., -3.), (5., 0.)));
+    }
+
+    #[test]
+    fn circle_to_rect_distance_across_the_corner() {
+        let r = Collidable::new_rect(0, 0, 0., 0., 4., 4.);
+        // Past the top-right corner, beyond both faces, the gap is measured to the vertex.
+        let c = Collidable::new_circle(0, 1, 7., 8., 1.);
+        assert!(close(r.distance(&c), 4.) && close(c.distance(&r), 4.));
+        let (p, q) = r.closest_points(&c);
+        assert!(close2(p, (4., 4.)) && close2(q, (6.4, 7.2)));
+        // Straight out from a face it's measured to the edge.
+        let side = Collidable::new_circle(0, 2, 7., 2., 1.);
+        assert!(close(r.distance(&side), 2.));
+
+        let overlapping = Collidable::new_circle(0, 3, 4.5, 4.5, 1.);
+        assert_eq!(r.distance(&overlapping), 0.);
+        assert_eq!(overlapping.distance(&r), 0.);
+        let inside = Collidable::new_circle(0, 4, 2., 2., 0.5);
+        assert_eq!(r.distance(&inside), 0.);
+    }
+
+    /// Checks `pieces` are convex, counter-clockwise, free of collinear vertices and tile
+    /// `poly`: their areas add up, and points well inside or outside `poly` agree.
+    fn tiles_convexly(poly: &Collidable, pieces: &[Collidable]) -> bool {
+        let convex = pieces.iter().all(|p| {
+            p.is_convex()
+                && p.signed_area() > 0.
+                && (0..p.nvert).all(|i| {
+                    let j = (i + 1) % p.nvert;
+                    let k = (i + 2) % p.nvert;
+                    let cross = (p.vertx[j] - p.vertx[i]) * (p.verty[k] - p.verty[j])
+                        - (p.verty[j] - p.verty[i]) * (p.vertx[k] - p.vertx[j]);
+                    cross.abs() > 1e-4
+                })
+        });
+        let area = pieces.iter().map(|p| p.area()).sum::<Real>();
+        let (minx, miny, maxx, maxy) = poly.aabb();
+        let samples = (0..40)
+            .flat_map(|i| (0..40).map(move |j| (i, j)))
+            .all(|(i, j)| {
+                let x = minx + (maxx - minx) * (i as Real + 0.5) / 40.;
+                let y = miny + (maxy - miny) * (j as Real + 0.5) / 40.;
+                let (nx, ny) = poly.nearest_point(x, y);
+                dist_sqrdf(x, y, nx, ny) < 1e-4
+                    || poly.contains_point(x, y) == pieces.iter().any(|p| p.contains_point(x, y))
+            });
+        convex && close(area, poly.area()) && samples
+    }
+
+    #[test]
+    fn decomposing_a_star_and_an_arrow() {
+        let mut vertx = vec![];
+        let mut verty = vec![];
+        for k in 0..10 {
+            let a = consts::PI / 2. + consts::PI * k as Real / 5.;
+            let r = if k % 2 == 0 { 2. } else { 0.8 };
+            vertx.push(r * a.cos());
+            verty.push(r * a.sin());
+        }
+        let star = Collidable::new_poly(0, 3, 10, vertx, verty);
+        let arrow = Collidable::new_poly(
+            0,
+            4,
+            7,
+            vec![0., 3., 3., 5., 3., 3., 0.],
+            vec![1., 1., 0., 1.5, 3., 2., 2.],
+        );
+        for shape in &[star, arrow.clone(), clockwise(arrow)] {
+            assert!(!shape.is_convex());
+            let pieces = shape.decompose_convex();
+            assert!(pieces.len() > 1);
+            assert!(pieces
+                .iter()
+                .all(|p| p.collidable_id == shape.collidable_id));
+            assert!(tiles_convexly(shape, &pieces));
+        }
+    }
+
+    #[test]
+    fn decomposing_a_convex_polygon_returns_it_whole() {
+        let hexagon = Collidable::new_regular(1, 2, 0., 0., 1., 6, 0.3);
+        assert_eq!(hexagon.decompose_convex(), vec![hexagon.clone()]);
+        let circle = Collidable::new_circle(1, 2, 0., 0., 1.);
+        assert_eq!(circle.decompose_convex(), vec![circle.clone()]);
+        // A square with a vertex midway along each side is still convex, and kept as given.
+        let square = Collidable::new_poly(
+            0,
+            0,
+            8,
+            vec![0., 1., 2., 2., 2., 1., 0., 0.],
+            vec![0., 0., 0., 1., 2., 2., 2., 1.],
+        );
+        assert_eq!(square.decompose_convex(), vec![square.clone()]);
+    }
+
+    #[test]
+    fn decomposing_drops_collinear_vertices() {
+        // The L shape with extra vertices along its bottom, inner and left edges.
+        let l = Collidable::new_poly(
+            0,
+            0,
+            9,
+            vec![0., 1., 2., 2., 1.5, 1., 1., 0., 0.],
+            vec![0., 0., 0., 1., 1., 1., 2., 2., 1.],
+        );
+        let pieces = l.decompose_convex();
+        assert!(pieces.len() > 1);
+        assert!(tiles_convexly(&l, &pieces));
+    }
+
+    #[test]
+    fn arrow_is_concave_and_a_hexagon_convex() {
+        let arrow = Collidable::new_poly(
+            0,
+            0,
+            7,
+            vec![0., 3., 3., 5., 3., 3., 0.],
+            vec![1., 1., 0., 1.5, 3., 2., 2.],
+        );
+        assert!(!arrow.is_convex() && !clockwise(arrow).is_convex());
+        let hexagon = Collidable::new_regular(0, 0, 0., 0., 1., 6, 0.);
+        assert!(hexagon.is_convex() && clockwise(hexagon).is_convex());
+        assert!(!l_shape().is_convex());
+        assert!(Collidable::new_circle(0, 0, 0., 0., 1.).is_convex());
+    }
+
+    /// Whether every edge normal points away from the polygon's centroid.
+    fn normals_face_out(c: &Collidable) -> bool {
+        let (cx, cy) = c.centroid();
+        (0..c.nvert).all(|i| {
+            let j = (i + 1) % c.nvert;
+            let mx = (c.vertx[i] + c.vertx[j]) * 0.5 - cx;
+            let my = (c.verty[i] + c.verty[j]) * 0.5 - cy;
+            c.normx[i] * mx + c.normy[i] * my > 0.
+        })
+    }
+
+    #[test]
+    fn ensure_ccw_flips_the_normals_of_clockwise_input() {
+        let mut cw = Collidable::new_poly(0, 0, 4, vec![0., 0., 2., 2.], vec![0., 2., 2., 0.]);
+        assert_eq!(cw.winding(), Winding::Clockwise);
+        assert!(!normals_face_out(&cw));
+        cw.ensure_ccw();
+        assert_eq!(cw.winding(), Winding::CounterClockwise);
+        assert!(normals_face_out(&cw));
+        assert!(cw.signed_area() > 0.);
+
+        // Already counter-clockwise input is left alone.
+        let ccw = Collidable::new_regular(0, 0, 0., 0., 1., 5, 0.);
+        let mut again = ccw.clone();
+        again.ensure_ccw();
+        assert_eq!(again, ccw);
+        assert!(normals_face_out(&again));
+    }
+
+    #[test]
+    fn area_of_a_unit_square_and_a_triangle() {
+        let square = Collidable::new_rect(0, 0, 3., -2., 1., 1.);
+        assert!(close(square.area(), 1.) && close(square.signed_area(), 1.));
+        let cw = clockwise(square);
+        assert!(close(cw.area(), 1.) && close(cw.signed_area(), -1.));
+
+        let tri = Collidable::new_poly(0, 0, 3, vec![0., 4., 0.], vec![0., 0., 3.]);
+        assert!(close(tri.area(), 6.) && close(tri.signed_area(), 6.));
+        assert!(close(clockwise(tri).signed_area(), -6.));
+        assert!(close(l_shape().area(), 3.));
+        assert!(close(
+            Collidable::new_circle(0, 0, 0., 0., 2.).area(),
+            4. * consts::PI
+        ));
+        assert_eq!(Collidable::new_segment(0, 0, 0., 0., 3., 4.).area(), 0.);
+    }
+
+    #[test]
+    fn l_shape_centroid_is_off_its_bounding_box_centre() {
+        let mut l = l_shape();
+        // Three unit squares centred at (0.5, 0.5), (1.5, 0.5) and (0.5, 1.5).
+        assert!(close2(l.centroid(), (5. / 6., 5. / 6.)));
+        assert!(close2((l.centrex, l.centrey), (1., 1.)));
+        assert!(close2(clockwise(l.clone()).centroid(), (5. / 6., 5. / 6.)));
+
+        let verts = (l.vertx.clone(), l.verty.clone());
+        l.set_centre_to_centroid();
+        assert!(close2((l.centrex, l.centrey), (5. / 6., 5. / 6.)));
+        assert_eq!((l.vertx.clone(), l.verty.clone()), verts);
+        // Rotating now turns the L about its centre of mass, which stays put.
+        l.rotate(1.);
+        assert!(close2(l.centroid(), (5. / 6., 5. / 6.)));
+    }
+
+    #[test]
+    fn try_new_poly_rejects_each_bad_layout() {
+        let err = |nvert: usize, vertx: Vec<Real>, verty: Vec<Real>| {
+            Collidable::try_new_poly(0, 0, nvert, vertx, verty).unwrap_err()
+        };
+        assert_eq!(
+            err(2, vec![0., 1.], vec![0., 1.]),
+            CollidableError::TooFewVertices
+        );
+        assert_eq!(err(0, vec![], vec![]), CollidableError::TooFewVertices);
+        assert_eq!(
+            err(4, vec![0., 1., 1.], vec![0., 0., 1.]),
+            CollidableError::MismatchedLengths
+        );
+        assert_eq!(
+            err(3, vec![0., 1., 1.], vec![0., 0., 1., 1.]),
+            CollidableError::MismatchedLengths
+        );
+        assert_eq!(
+            err(3, vec![0., Real::INFINITY, 1.], vec![0., 0., 1.]),
+            CollidableError::NonFiniteCoordinate
+        );
+        assert_eq!(
+            err(3, vec![0., 1., 1.], vec![0., 0., Real::NEG_INFINITY]),
+            CollidableError::NonFiniteCoordinate
+        );
+        assert_eq!(
+            err(3, vec![0., 1., 2.], vec![0., 1., 2.]),
+            CollidableError::ZeroArea
+        );
+        let ok = Collidable::try_new_poly(0, 0, 3, vec![0., 1., 1.], vec![0., 0., 1.]).unwrap();
+        assert_eq!(
+            ok,
+            Collidable::new_poly(0, 0, 3, vec![0., 1., 1.], vec![0., 0., 1.])
+        );
+    }
+
+    #[test]
+    fn nan_vertex_is_rejected_rather_than_measured() {
+        let nan = Collidable::try_new_poly(0, 0, 3, vec![0., Real::NAN, 1.], vec![0., 0., 1.]);
+        assert_eq!(nan.unwrap_err(), CollidableError::NonFiniteCoordinate);
+        let nan = Collidable::try_new_poly(0, 0, 3, vec![0., 1., 1.], vec![Real::NAN, 0., 1.]);
+        assert_eq!(nan.unwrap_err(), CollidableError::NonFiniteCoordinate);
+    }
+
+    #[test]
+    #[should_panic(expected = "vertex coordinates must be finite")]
+    fn new_poly_panics_on_a_nan_vertex() {
+        Collidable::new_poly(0, 0, 3, vec![Real::NAN, 1., 0.], vec![0., 0., 1.]);
+    }
+
+    #[test]
+    fn finer_arcs_have_more_vertices_and_end_on_the_end_angle() {
+        let (r, rad) = (2., consts::PI * 0.75);
+        let end = consts::PI / 2. + rad / 2.;
+        let mut last = 0;
+        for &segments in &[8, 16, 64, 256] {
+            let arc = Collidable::new_arc_with_segments(0, 0, 1., 1., r, 0., 1., rad, segments);
+            assert!(arc.nvert > last, "{} segments", segments);
+            last = arc.nvert;
+            assert!(close2((arc.vertx[0], arc.verty[0]), (1., 1.)));
+            let k = arc.nvert - 1;
+            assert!(close2(
+                (arc.vertx[k], arc.verty[k]),
+                (1. + r * end.cos(), 1. + r * end.sin())
+            ));
+            // Every arc vertex lies on the circle.
+            assert!((1..arc.nvert)
+                .all(|i| close(dist_sqrdf(arc.vertx[i], arc.verty[i], 1., 1.), r * r)));
+        }
+        let default = Collidable::new_arc(0, 0, 1., 1., r, 0., 1., rad);
+        assert_eq!(
+            default,
+            Collidable::new_arc_with_segments(0, 0, 1., 1., r, 0., 1., rad, 16)
+        );
+    }
+
+    #[test]
+    fn projecting_a_rect_onto_x_gives_its_side_extents() {
+        let r = Collidable::new_rect(0, 0, -1., 2., 5., 3.);
+        assert!(close2(r.project_onto_axis(1., 0.), (-1., 4.)));
+        assert!(close2(r.project_onto_axis(0., 1.), (2., 5.)));
+        assert!(close2(r.project_onto_axis(-1., 0.), (-4., 1.)));
+        // A circle's interval is its centre widened by the radius.
+        let c = Collidable::new_circle(0, 0, 3., 0., 2.);
+        assert!(close2(c.project_onto_axis(1., 0.), (1., 5.)));
+    }
+
+    #[test]
+    fn a_rect_contains_a_circle_until_it_pokes_out() {
+        let r = Collidable::new_rect(0, 0, 0., 0., 10., 6.);
+        assert!(r.contains(&Collidable::new_circle(0, 1, 5., 3., 2.)));
+        assert!(r.contains(&Collidable::new_circle(0, 1, 8.9, 3., 1.)));
+        assert!(!r.contains(&Collidable::new_circle(0, 1, 9.5, 3., 1.)));
+        assert!(!r.contains(&Collidable::new_circle(0, 1, 5., 3., 3.5)));
+        assert!(!r.contains(&Collidable::new_circle(0, 1, 20., 3., 1.)));
+        assert!(r.contains(&Collidable::new_rect(0, 1, 1., 1., 2., 2.)));
+        assert!(!Collidable::new_circle(0, 1, 5., 3., 2.).contains(&r));
+    }
+
+    #[test]
+    fn set_vertex_moves_a_corner_and_its_two_normals() {
+        let mut sq = Collidable::new_rect(0, 0, 0., 0., 2., 2.);
+        sq.set_vertex(1, 3., 0.);
+        assert!(close(sq.width, 3.) && close(sq.height, 2.));
+        assert!(close2((sq.centrex, sq.centrey), (1.5, 1.)));
+        assert_eq!(sq.aabb(), (0., 0., 3., 2.));
+        // The fast path patches the two edges at the corner; a full recompute agrees.
+        let mut full = sq.clone();
+        full.update_normals();
+        assert_eq!((&sq.normx, &sq.normy), (&full.normx, &full.normy));
+
+        // The first vertex wraps to the closing edge.
+        sq.set_vertex(0, -1., -1.);
+        let mut full = sq.clone();
+        full.update_normals();
+        assert_eq!((&sq.normx, &sq.normy), (&full.normx, &full.normy));
+        assert!(close(sq.width, 4.) && close(sq.height, 3.));
+    }
+
+    #[test]
+    fn update_bounds_after_moving_vertices_by_hand() {
+        let mut r = Collidable::new_rect(0, 0, 0., 0., 2., 1.);
+        for x in &mut r.vertx {
+            *x += 5.;
+        }
+        r.verty[2] = 4.;
+        r.verty[3] = 4.;
+        r.update_bounds();
+        assert_eq!(r.aabb(), (5., 0., 7., 4.));
+        assert!(close(r.width, 2.) && close(r.height, 4.));
+        assert!(close2((r.centrex, r.centrey), (6., 2.)));
+        assert!(r.collides(&Collidable::new_circle(0, 1, 6., 3., 0.5)));
+    }
+
+    #[test]
+    fn simplify_collinear_drops_a_midpoint_from_a_square() {
+        let mut sq =
+            Collidable::new_poly(0, 0, 5, vec![0., 1., 2., 2., 0.], vec![0., 0., 0., 2., 2.]);
+        sq.simplify_collinear(1e-4);
+        assert_eq!(sq, Collidable::new_rect(0, 0, 0., 0., 2., 2.));
+
+        // A real corner is kept, even a shallow one above the tolerance.
+        let mut kinked = Collidable::new_poly(
+            0,
+            0,
+            5,
+            vec![0., 1., 2., 2., 0.],
+            vec![0., -0.1, 0., 2., 2.],
+        );
+        kinked.simplify_collinear(1e-4);
+        assert_eq!(kinked.nvert, 5);
+        kinked.simplify_collinear(0.3);
+        assert_eq!(kinked.nvert, 4);
+    }
+
+    #[test]
+    fn simplify_rdp_coarsens_a_fine_circle_within_tolerance() {
+        let fine = Collidable::new_regular(0, 0, 0., 0., 10., 256, 0.);
+        let tolerance = 0.5;
+        let mut coarse = fine.clone();
+        coarse.simplify_rdp(tolerance);
+        assert!(
+            coarse.nvert >= 8 && coarse.nvert < 32,
+            "{} vertices",
+            coarse.nvert
+        );
+        assert!(coarse.is_convex() && coarse.validate().is_ok());
+        // Every dropped vertex stays within the tolerance of the simplified outline, and
+        // the kept ones are original vertices.
+        for i in 0..fine.nvert {
+            let (x, y) = coarse.nearest_point(fine.vertx[i], fine.verty[i]);
+            assert!(dist_sqrdf(x, y, fine.vertx[i], fine.verty[i]) <= sqrf(tolerance) + 1e-4);
+        }
+        assert!((0..coarse.nvert).all(|k| {
+            (0..fine.nvert)
+                .any(|i| coarse.vertx[k] == fine.vertx[i] && coarse.verty[k] == fine.verty[i])
+        }));
+        assert!((coarse.area() - fine.area()).abs() < fine.perimeter() * tolerance);
+    }
+
+    #[test]
+    fn inflating_a_unit_square_by_a_half() {
+        let mut sq = Collidable::new_rect(0, 0, 0., 0., 1., 1.);
+        assert!(sq.inflate(0.5));
+        assert!(sq.approx_eq(&Collidable::new_rect(0, 0, -0.5, -0.5, 2., 2.), 1e-4));
+        assert!(sq.inflate(-0.5));
+        assert!(sq.approx_eq(&Collidable::new_rect(0, 0, 0., 0., 1., 1.), 1e-4));
+        let mut cw = clockwise(Collidable::new_rect(0, 0, 0., 0., 1., 1.));
+        assert!(cw.inflate(0.5));
+        assert!(close(cw.area(), 4.));
+
+        // Shrinking past the middle would turn the edges inside out, so it's refused.
+        let before = sq.clone();
+        assert!(!sq.inflate(-0.6));
+        assert_eq!(sq, before);
+        let mut c = Collidable::new_circle(0, 0, 0., 0., 1.);
+        assert!(!c.inflate(-1.5));
+        assert!(close(c.radius, 1.));
+    }
+
+    #[test]
+    fn a_tiny_gap_collides_only_within_a_tolerance() {
+        let a = Collidable::new_rect(0, 0, 0., 0., 1., 1.);
+        let b = Collidable::new_rect(0, 1, 1. + 1e-6, 0., 1., 1.);
+        assert!(!a.collides(&b));
+        assert!(!a.collides_within(&b, 0.));
+        assert!(a.collides_within(&b, 1e-3) && b.collides_within(&a, 1e-3));
+        let far = Collidable::new_rect(0, 2, 1.01, 0., 1., 1.);
+        assert!(!a.collides_within(&far, 1e-3));
+    }
+
+    #[test]
+    fn overlap_area_of_half_overlapping_contained_and_touching_squares() {
+        let a = Collidable::new_rect(0, 0, 0., 0., 1., 1.);
+        let half = Collidable::new_rect(0, 1, 0.5, 0., 1., 1.);
+        assert!(close(a.overlap_area(&half), 0.5) && close(half.overlap_area(&a), 0.5));
+        assert!(close(clockwise(a.clone()).overlap_area(&half), 0.5));
+        let inner = Collidable::new_rect(0, 2, 0.25, 0.25, 0.5, 0.5);
+        assert!(close(a.overlap_area(&inner), 0.25) && close(inner.overlap_area(&a), 0.25));
+        let touching = Collidable::new_rect(0, 3, 1., 0., 1., 1.);
+        assert!(close(a.overlap_area(&touching), 0.));
+        let apart = Collidable::new_rect(0, 4, 3., 0., 1., 1.);
+        assert_eq!(a.overlap_area(&apart), 0.);
+        assert!(close(a.overlap_area(&a), 1.));
+    }
+
+    #[test]
+    fn from_points_wraps_a_noisy_cloud_in_its_corners() {
+        let mut rng = Rng(11);
+        let mut points: Vec<(Real, Real)> = (0..200)
+            .map(|_| (rng.range(0.01, 3.99), rng.range(0.01, 3.99)))
+            .collect();
+        points.extend_from_slice(&[(4., 4.), (0., 0.), (4., 0.), (0., 4.)]);
+        // Points on the edges are dropped too.
+        points.extend_from_slice(&[(2., 0.), (4., 1.5), (0., 3.)]);
+        let hull = Collidable::from_points(0, 0, &points);
+        assert_eq!(hull, Collidable::new_rect(0, 0, 0., 0., 4., 4.));
+    }
+
+    #[test]
+    fn a_rects_edges_close_the_loop() {
+        let r = Collidable::new_rect(0, 0, 0., 0., 2., 1.);
+        let edges: Vec<_> = r.edges().collect();
+        assert_eq!(
+            edges,
+            vec![
+                ((0., 0.), (2., 0.)),
+                ((2., 0.), (2., 1.)),
+                ((2., 1.), (0., 1.)),
+                ((0., 1.), (0., 0.))
+            ]
+        );
+        assert!(edges.windows(2).all(|w| w[0].1 == w[1].0));
+        let capsule = Collidable::new_capsule(0, 0, 0., 0., 3., 0., 1.);
+        assert_eq!(
+            capsule.edges().collect::<Vec<_>>(),
+            vec![((0., 0.), (3., 0.))]
+        );
+        assert_eq!(Collidable::new_circle(0, 0, 0., 0., 1.).edges().count(), 0);
+    }
+
+    #[test]
+    fn vertices_and_normals_yield_one_per_vertex() {
+        let shapes = vec![
+            Collidable::new_rect(0, 0, 0., 0., 2., 1.),
+            l_shape(),
+            Collidable::new_regular(0, 0, 0., 0., 1., 7, 0.),
+            Collidable::new_capsule(0, 0, 0., 0., 3., 0., 1.),
+        ];
+        for c in &shapes {
+            let verts: Vec<_> = c.vertices().collect();
+            assert_eq!(verts.len(), c.nvert);
+            assert!(verts
+                .iter()
+                .enumerate()
+                .all(|(i, v)| v.x == c.vertx[i] && v.y == c.verty[i]));
+            let norms: Vec<_> = c.normals().collect();
+            assert_eq!(norms.len(), c.nvert);
+            assert!(norms
+                .iter()
+                .enumerate()
+                .all(|(i, n)| n.x == c.normx[i] && n.y == c.normy[i]));
+        }
+        let circle = Collidable::new_circle(0, 0, 0., 0., 1.);
+        assert_eq!(
+            (circle.vertices().count(), circle.normals().count()),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn poly_from_points_or_vectors_matches_new_poly() {
+        let (vertx, verty) = (vec![0., 2., 2., 1., 1., 0.], vec![0., 0., 1., 1., 2., 2.]);
+        let points: Vec<(Real, Real)> = vertx.iter().cloned().zip(verty.iter().cloned()).collect();
+        let vectors: Vec<Vector2<Real>> = points.iter().map(|&(x, y)| Vector2::new(x, y)).collect();
+        let poly = Collidable::new_poly(3, 4, 6, vertx, verty);
+        assert_eq!(Collidable::new_poly_from_points(3, 4, &points), poly);
+        assert_eq!(Collidable::new_poly_from_vectors(3, 4, &vectors), poly);
+        assert_eq!(poly, l_shape().clone_with(4, 3));
+    }
+
+    #[test]
+    fn builder_shapes_match_their_constructors() {
+        let circle = CollidableBuilder::new()
+            .collidable_type(2)
+            .id(7)
+            .circle(1., -1., 3.)
+            .build();
+        assert_eq!(circle, Ok(Collidable::new_circle(2, 7, 1., -1., 3.)));
+        let rect = CollidableBuilder::new().id(1).rect(0., 0., 2., 1.).build();
+        assert_eq!(rect, Ok(Collidable::new_rect(0, 1, 0., 0., 2., 1.)));
+        let points = [(0., 0.), (2., 0.), (2., 1.), (1., 1.), (1., 2.), (0., 2.)];
+        let poly = CollidableBuilder::new().polygon(&points).build();
+        assert_eq!(poly, Ok(l_shape()));
+        // The last shape given wins.
+        let last = CollidableBuilder::new()
+            .rect(0., 0., 1., 1.)
+            .circle(0., 0., 1.)
+            .build();
+        assert_eq!(last, Ok(Collidable::new_circle(0, 0, 0., 0., 1.)));
+    }
+
+    #[test]
+    fn every_mutator_keeps_the_cached_aabb_current() {
+        let shapes = vec![
+            Collidable::new_rect(0, 0, 0.3, -1.7, 2., 1.),
+            l_shape(),
+            Collidable::new_circle(0, 0, 1.1, 2.2, 0.7),
+            Collidable::new_capsule(0, 0, 0., 0., 3., 1., 0.5),
+            Collidable::new_ellipse(0, 0, 1., 1., 2., 0.5),
+            Collidable::new_arc(0, 0, 0., 0., 2., 1., 0., 2.),
+            Collidable::new_regular(0, 0, 0., 0., 5., 64, 0.1),
+        ];
+        type Step<'a> = (&'a str, &'a dyn Fn(&mut Collidable));
+        let steps: Vec<Step> = vec![
+            ("translate", &|c| c.translate(0.37, -1.13)),
+            ("rotate", &|c| c.rotate(0.7)),
+            ("scale", &|c| c.scale(1.3, 0.6)),
+            ("set_vertex", &|c| {
+                if c.nvert > 2 {
+                    let (x, y) = (c.vertx[1] + 0.4, c.verty[1] - 0.2);
+                    c.set_vertex(1, x, y);
+                }
+            }),
+            ("update_bounds", &|c| {
+                for x in &mut c.vertx {
+                    *x -= 0.25;
+                }
+                c.update_bounds();
+            }),
+            ("flip_x", &|c| c.flip_x(0.5)),
+            ("flip_y", &|c| c.flip_y(-2.)),
+            ("inflate", &|c| {
+                c.inflate(0.2);
+            }),
+            ("simplify_rdp", &|c| c.simplify_rdp(0.05)),
+            ("set_position", &|c| c.set_position(-3.3, 7.9)),
+        ];
+        for shape in &shapes {
+            let mut c = shape.clone();
+            for &(name, step) in &steps {
+                step(&mut c);
+                assert_eq!(
+                    c.aabb(),
+                    c.compute_aabb(),
+                    "{} on {:?}",
+                    name,
+                    shape.collidable_shape
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn a_thousand_gon_pushed_vertex_by_vertex_matches_new_poly() {
+        let n = 1000;
+        let mut pushed = Collidable::with_capacity(1, 2, n);
+        let (mut vertx, mut verty) = (vec![], vec![]);
+        for k in 0..n {
+            let a = 2. * consts::PI * k as Real / n as Real;
+            let r = if k % 2 == 0 { 10. } else { 9.5 };
+            pushed.push_vertex(r * a.cos(), r * a.sin());
+            vertx.push(r * a.cos());
+            verty.push(r * a.sin());
+        }
+        pushed.finish();
+        let built = Collidable::new_poly(1, 2, n, vertx, verty);
+        assert_eq!(pushed, built);
+        assert_eq!((&pushed.normx, &pushed.normy), (&built.normx, &built.normy));
+        assert_eq!(pushed.aabb(), built.aabb());
+        // The reserved room was enough, so nothing reallocated past it.
+        assert!(pushed.vertx.capacity() == n && pushed.normx.capacity() == n);
+    }
+
+    #[test]
+    fn shrink_to_fit_after_simplifying_releases_memory() {
+        let size = mem::size_of::<Real>();
+        let mut c = Collidable::new_regular(0, 0, 0., 0., 10., 512, 0.);
+        let before = c.memory_bytes();
+        assert!(before >= 4 * 512 * size);
+        c.simplify_rdp(0.5);
+        c.shrink_to_fit();
+        assert!(c.nvert < 64);
+        assert_eq!(c.memory_bytes(), 4 * c.nvert * size);
+        assert!(c.memory_bytes() < before / 8);
+        assert!(c.validate().is_ok());
+
+        // `simplify_collinear` removes vertices in place, so there the shrink is what
+        // gives the room back.
+        let mut sq = Collidable::with_capacity(0, 0, 256);
+        for &(x, y) in &[(0., 0.), (1., 0.), (2., 0.), (2., 2.), (0., 2.)] {
+            sq.push_vertex(x, y);
+        }
+        sq.finish();
+        sq.simplify_collinear(1e-4);
+        assert_eq!(sq.nvert, 4);
+        let roomy = sq.memory_bytes();
+        sq.shrink_to_fit();
+        assert!(sq.memory_bytes() < roomy);
+        assert_eq!(sq.memory_bytes(), 4 * 4 * size);
+    }
+
+    #[test]
+    fn clones_with_a_new_id_or_type_share_the_geometry() {
+        let mut l = l_shape().with_data("payload");
+        l.margin = 0.25;
+        let copy = l.clone_with_id(9);
+        assert_eq!(
+            (copy.collidable_id, copy.collidable_type),
+            (9, l.collidable_type)
+        );
+        assert!(copy.approx_eq(&l, 0.) && copy.margin == l.margin && copy.data == "payload");
+        let retyped = l.clone_with(10, 4);
+        assert_eq!((retyped.collidable_id, retyped.collidable_type), (10, 4));
+        assert!(retyped.approx_eq(&l, 0.));
+        assert_eq!((&retyped.normx, &retyped.normy), (&l.normx, &l.normy));
+        assert_eq!(retyped.aabb(), l.aabb());
+        // The clones are independent copies.
+        let mut moved = l.clone_with_id(11);
+        moved.translate(1., 0.);
+        assert!(close(l.vertx[0], 0.));
+    }
+
+    #[test]
+    fn set_position_moves_the_bounds_and_centroid() {
+        let mut r = Collidable::new_rect(0, 0, 0., 0., 4., 2.);
+        r.set_position(10., -5.);
+        assert_eq!((r.centrex, r.centrey), (10., -5.));
+        assert_eq!(r.aabb(), (8., -6., 12., -4.));
+        assert!(close2(r.centroid(), (10., -5.)));
+
+        // An L's centre is its bounding box middle, so the centroid lands off it.
+        let mut l = l_shape();
+        l.set_position(0., 0.);
+        assert_eq!(l.aabb(), (-1., -1., 1., 1.));
+        assert!(close2(l.centroid(), (-1. / 6., -1. / 6.)));
+        let mut c = Collidable::new_circle(0, 0, 1., 1., 2.);
+        c.set_position(-3., 4.);
+        assert_eq!(c.aabb(), (-5., 2., -1., 6.));
+    }
+
+    #[test]
+    fn a_bullet_box_hits_a_thin_wall_early_in_the_step() {
+        let wall = Collidable::new_rect(0, 0, 50., -5., 0.5, 15.);
+        let bullet = Collidable::new_rect(0, 1, 0., 0., 1., 1.);
+        // The start and end positions both miss the wall.
+        let mut end = bullet.clone();
+        end.translate(100., 0.);
+        assert!(!bullet.collides(&wall) && !end.collides(&wall));
+        let t = bullet
+            .time_of_impact((100., 0.), &wall, (0., 0.))
+            .expect("hits the wall");
+        assert!((t - 0.49).abs() < 1e-3, "t = {}", t);
+        // Only the relative velocity counts.
+        let t2 = bullet.time_of_impact((60., 0.), &wall, (-40., 0.)).unwrap();
+        assert!((t2 - t).abs() < 1e-3);
+        assert!(bullet
+            .time_of_impact((-100., 0.), &wall, (0., 0.))
+            .is_none());
+        assert!(bullet.time_of_impact((40., 0.), &wall, (0., 0.)).is_none());
+    }
+
+    #[test]
+    fn grazing_motion_past_a_wall() {
+        let wall = Collidable::new_rect(0, 0, 50., -5., 0.5, 15.);
+        // Skimming 0.1 over the top never touches.
+        let over = Collidable::new_rect(0, 1, 0., 10.1, 1., 1.);
+        assert!(over.time_of_impact((100., 0.), &wall, (0., 0.)).is_none());
+        // Clipping the top by 0.1 still catches the corner.
+        let clip = Collidable::new_rect(0, 1, 0., 9.9, 1., 1.);
+        let t = clip
+            .time_of_impact((100., 0.), &wall, (0., 0.))
+            .expect("clips the corner");
+        assert!((t - 0.49).abs() < 1e-3);
+        // Sliding along the face, parallel to it, never closes the gap.
+        let beside = Collidable::new_rect(0, 1, 48.9, -20., 1., 1.);
+        assert!(beside.time_of_impact((0., 100.), &wall, (0., 0.)).is_none());
+        // Moving away from a shape already in reach reports 0.
+        let touching = Collidable::new_rect(0, 1, 49., 0., 1., 1.);
+        assert_eq!(
+            touching.time_of_impact((-10., 0.), &wall, (0., 0.)),
+            Some(0.)
+        );
+    }
+
+    #[test]
+    fn resolve_leaves_overlapping_circles_tangent() {
+        // Along an axis every step is exact, so they end up precisely touching.
+        let mut a = Collidable::new_circle(0, 0, 0., 0., 1.);
+        let mut b = Collidable::new_circle(0, 1, 1.5, 0., 1.);
+        a.resolve(&mut b);
+        assert_eq!((a.centrex, b.centrex), (-0.25, 1.75));
+        assert!(!a.collides(&b) && !b.collides(&a));
+        assert!(a.collision_mtv(&b).is_none());
+
+        // Each moves half way along the line between the centres.
+        let mut a = Collidable::new_circle(0, 0, 0., 0., 1.);
+        let mut b = Collidable::new_circle(0, 1, 1.2, 0.9, 1.);
+        a.resolve(&mut b);
+        let d = dist_sqrdf(a.centrex, a.centrey, b.centrex, b.centrey).sqrt();
+        assert!(close(d, 2.));
+        assert!(close2((a.centrex, a.centrey), (-0.2, -0.15)));
+        assert!(close2((b.centrex, b.centrey), (1.4, 1.05)));
+
+        // A heavier body barely moves.
+        let mut light = Collidable::new_circle(0, 0, 0., 0., 1.);
+        let mut heavy = Collidable::new_circle(0, 1, 1.5, 0., 1.);
+        light.resolve_weighted(&mut heavy, 1., 999.);
+        assert!(close(heavy.centrex - light.centrex, 2.));
+        assert!(close(light.centrex, -0.4995) && close(heavy.centrex, 1.5005));
+    }
+
+    #[test]
+    fn support_of_a_square_toward_one_one_is_its_top_right_corner() {
+        let sq = Collidable::new_rect(0, 0, 1., 1., 2., 2.);
+        assert!(close2(sq.support(1., 1.), (3., 3.)));
+        assert!(close2(sq.support(-1., -1.), (1., 1.)));
+        assert!(close2(sq.support(1., -0.5), (3., 1.)));
+        assert!(close2(clockwise(sq).support(1., 1.), (3., 3.)));
+        let c = Collidable::new_circle(0, 0, 0., 0., 2.);
+        let h = consts::SQRT_2;
+        assert!(close2(c.support(1., 1.), (h, h)));
+    }
+
+    #[test]
+    fn subdividing_a_square_once_doubles_its_vertices() {
+        let sq = Collidable::new_rect(0, 0, 0., 0., 2., 2.);
+        let mut fine = sq.clone();
+        fine.subdivide(1);
+        assert_eq!(fine.nvert, 8);
+        assert_eq!((fine.vertx[1], fine.verty[1]), (1., 0.));
+        assert_eq!(fine.aabb(), sq.aabb());
+        assert!(close2(
+            (fine.centrex, fine.centrey),
+            (sq.centrex, sq.centrey)
+        ));
+        assert!(close(fine.area(), sq.area()));
+        assert!(fine.validate().is_ok());
+        fine.subdivide(2);
+        assert_eq!(fine.nvert, 32);
+        fine.simplify_collinear(1e-4);
+        assert_eq!(fine, sq);
+    }
+
+    #[test]
+    fn nearest_edge_just_outside_a_wall_and_on_a_tie() {
+        let r = Collidable::new_rect(0, 0, 0., 0., 4., 2.);
+        // Edges run bottom, right, top, left.
+        assert_eq!(r.nearest_edge(2., -0.1), Some(0));
+        assert_eq!(r.nearest_edge(4.1, 1.), Some(1));
+        assert_eq!(r.nearest_edge(2., 2.1), Some(2));
+        assert_eq!(r.nearest_edge(-0.1, 1.), Some(3));
+        // Past a corner both edges are the same distance away; the lower index wins.
+        assert_eq!(r.nearest_edge(5., 3.), Some(1));
+        assert_eq!(r.nearest_edge(-1., -1.), Some(0));
+        assert_eq!(
+            Collidable::new_circle(0, 0, 0., 0., 1.).nearest_edge(2., 0.),
+            None
+        );
+    }
+
+    #[test]
+    fn collides_which_picks_out_the_overlapped_shapes() {
+        let others: Vec<Collidable> = (0..5)
+            .map(|i| Collidable::new_rect(0, i, i as Real * 3., 0., 2., 2.))
+            .collect();
+        // Spans the gap between the second and third rects.
+        let query = Collidable::new_circle(0, 9, 5.5, 1., 1.);
+        assert_eq!(query.collides_which(&others), vec![1, 2]);
+        assert!(query.collides_any(&others));
+        let miss = Collidable::new_circle(0, 9, 5.5, 10., 1.);
+        assert!(miss.collides_which(&others).is_empty() && !miss.collides_any(&others));
+        assert!(!query.collides_any(&others[3..]));
+        assert!(query.collides_which::<()>(&[]).is_empty());
+    }
+
+    #[test]
+    fn from_aabb_encloses_the_shape_it_came_from() {
+        let shapes = vec![
+            l_shape(),
+            Collidable::new_circle(0, 0, 2., -1., 1.5),
+            Collidable::new_regular(0, 0, 0., 0., 3., 5, 0.3),
+            Collidable::new_capsule(0, 0, 0., 0., 4., 2., 0.5),
+        ];
+        for c in &shapes {
+            let (min_x, min_y, max_x, max_y) = c.aabb();
+            let b = Collidable::from_aabb(0, 1, min_x, min_y, max_x, max_y);
+            let (a, e) = (b.aabb(), c.aabb());
+            assert!(close2((a.0, a.1), (e.0, e.1)) && close2((a.2, a.3), (e.2, e.3)));
+            assert!(b.contains(c));
+        }
+        // Swapped corners give the same box.
+        let swapped = Collidable::from_aabb(0, 1, 3., 4., -1., 2.);
+        assert_eq!(swapped, Collidable::new_rect(0, 1, -1., 2., 4., 2.));
+        assert_eq!(swapped, Collidable::from_aabb(0, 1, -1., 2., 3., 4.));
+    }
+
+    #[test]
+    fn margins_count_for_the_mtv_and_resolve() {
+        let mut a = Collidable::new_rect(0, 0, 0., 0., 1., 1.);
+        let mut b = Collidable::new_rect(0, 1, 1.15, 0., 1., 1.);
+        a.margin = 0.1;
+        b.margin = 0.1;
+        assert!(a.collides(&b) && a.manifold(&b).is_some());
+        assert!(close2(a.collision_mtv(&b).unwrap(), (-0.05, 0.)));
+        assert!(close2(b.collision_mtv(&a).unwrap(), (0.05, 0.)));
+        a.resolve(&mut b);
+        assert!(close(a.distance(&b), 0.2));
+        // They're left with their margins touching, give or take rounding.
+        let rest = a.collision_mtv(&b).unwrap_or((0., 0.));
+        assert!(rest.0.hypot(rest.1) < 1e-5);
+
+        // Overlapping shapes are pushed past contact by the margins as well.
+        let mut c = Collidable::new_circle(0, 0, 0., 0., 1.);
+        let mut d = Collidable::new_circle(0, 1, 1.5, 0., 1.);
+        c.margin = 0.25;
+        assert!(close2(c.collision_mtv(&d).unwrap(), (-0.75, 0.)));
+        c.resolve(&mut d);
+        assert!(close(d.centrex - c.centrex, 2.25));
+    }
+
+    fn triangle_area(t: &[(Real, Real); 3]) -> Real {
+        ((t[1].0 - t[0].0) * (t[2].1 - t[0].1) - (t[2].0 - t[0].0) * (t[1].1 - t[0].1)) * 0.5
+    }
+
+    #[test]
+    fn triangulating_a_square_and_a_circle() {
+        let sq = Collidable::new_rect(0, 0, 0., 0., 3., 2.);
+        let tris = sq.triangulate();
+        assert_eq!(tris.len(), 2);
+        assert!(tris.iter().all(|t| triangle_area(t) > 0.));
+        assert!(close(tris.iter().map(triangle_area).sum(), 6.));
+        let l = clockwise(l_shape()).triangulate();
+        assert_eq!(l.len(), 4);
+        assert!(close(l.iter().map(triangle_area).sum(), 3.));
+
+        // A circle becomes a fan of thin triangles around its centre.
+        let circle = Collidable::new_circle(0, 0, 1., 2., 2.);
+        let fan = circle.triangulate();
+        assert!(fan.len() >= 16);
+        assert!(fan
+            .iter()
+            .all(|t| t[0] == (1., 2.) && triangle_area(t) > 0.));
+        assert!(fan.windows(2).all(|w| w[0][2] == w[1][1]));
+        let area = fan.iter().map(triangle_area).sum::<Real>();
+        assert!(area < circle.area() && area > circle.area() * 0.98);
+        assert!(Collidable::new_segment(0, 0, 0., 0., 1., 1.)
+            .triangulate()
+            .is_empty());
+    }
+
+    #[test]
+    fn closest_points_of_separated_circles_lie_between_the_centres() {
+        let a = Collidable::new_circle(0, 0, 0., 0., 1.);
+        let b = Collidable::new_circle(0, 1, 6., 8., 2.);
+        let (p, q) = a.closest_points(&b);
+        assert!(close2(p, (0.6, 0.8)) && close2(q, (4.8, 6.4)));
+        assert!(close(dist_sqrdf(p.0, p.1, q.0, q.1).sqrt(), a.distance(&b)));
+        // Both are on the segment joining the centres, so crossing with it gives 0.
+        for &(x, y) in &[p, q] {
+            assert!(close(x * 8. - y * 6., 0.));
+        }
+        let (q2, p2) = b.closest_points(&a);
+        assert!(close2(p2, p) && close2(q2, q));
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Enemy {
+        name: String,
+        health: u32,
+    }
+
+    #[test]
+    fn a_custom_payload_survives_clone_and_translate() {
+        let enemy = Enemy {
+            name: "slime".to_string(),
+            health: 30,
+        };
+        let mut c = Collidable::new_circle(0, 0, 0., 0., 1.).with_data(enemy.clone());
+        let copy = c.clone();
+        c.translate(5., 0.);
+        c.rotate(1.);
+        assert_eq!(c.data, enemy);
+        assert_eq!(copy.data, enemy);
+        assert!(close(c.centrex, 5.) && close(copy.centrex, 0.));
+        c.data.health -= 10;
+        assert_eq!((c.data.health, copy.data.health), (20, 30));
+        assert!(c.collides(&Collidable::new_circle(0, 1, 6., 0., 1.)));
+        assert_eq!(c.clone_with_id(4).data, c.data);
+    }
+
+    #[test]
+    fn two_small_rotations_match_one_large_one() {
+        let shapes = vec![
+            Collidable::new_rect(0, 0, 1., 1., 3., 2.),
+            l_shape(),
+            Collidable::new_capsule(0, 0, 0., 0., 3., 1., 0.5),
+            Collidable::new_circle(0, 0, 2., 0., 1.),
+        ];
+        for shape in &shapes {
+            let mut twice = shape.clone();
+            twice.rotate(0.1);
+            twice.rotate(0.1);
+            assert!(close(twice.angle, 0.2));
+            let mut once = shape.clone();
+            once.rotate(0.2);
+            assert!(twice.approx_eq(&once, 1e-5));
+            assert!(close(once.angle, 0.2));
+        }
+    }
+
+    #[test]
+    fn capsule_on_flat_ground_is_pushed_straight_up() {
+        let ground = Collidable::new_rect(0, 0, -10., -2., 20., 2.);
+        // Standing: the bottom cap sinks 0.1 into the ground.
+        let standing = Collidable::new_capsule(0, 1, 0., 0.4, 0., 2., 0.5);
+        assert!(standing.collides(&ground));
+        assert!(close2(standing.collision_mtv(&ground).unwrap(), (0., 0.1)));
+        // Lying along the ground, the whole spine is the same depth in.
+        let lying = Collidable::new_capsule(0, 2, -2., 0.3, 2., 0.3, 0.5);
+        assert!(close2(lying.collision_mtv(&ground).unwrap(), (0., 0.2)));
+        let m = lying.manifold(&ground).unwrap();
+        assert!(close2(m.normal, (0., -1.)) && close(m.depth, 0.2));
+        // Hovering just above, nothing.
+        let above = Collidable::new_capsule(0, 3, 0., 0.6, 0., 2., 0.5);
+        assert!(!above.collides(&ground) && above.collision_mtv(&ground).is_none());
+    }
+
+    #[test]
+    fn capsule_on_a_slope_is_pushed_along_its_normal() {
+        // A 45 degree ramp rising to the right, its surface the line y = x.
+        let ramp = Collidable::new_poly(0, 0, 3, vec![-10., 10., 10.], vec![-10., -10., 10.]);
+        let h = 1. / consts::SQRT_2;
+        // A standing capsule whose bottom cap centre sits 0.3 from the surface.
+        let (bx, by) = (-0.3 * h, 0.3 * h);
+        let standing = Collidable::new_capsule(0, 1, bx, by, bx, by + 2., 0.5);
+        let (mx, my) = standing.collision_mtv(&ramp).unwrap();
+        assert!(close2((mx, my), (-0.2 * h, 0.2 * h)));
+        // Lying along the slope, the push is the same.
+        let lying = Collidable::new_capsule(0, 2, bx - 1., by - 1., bx + 1., by + 1., 0.5);
+        assert!(close2(
+            lying.collision_mtv(&ramp).unwrap(),
+            (-0.2 * h, 0.2 * h)
+        ));
+        let mut moved = lying.clone();
+        moved.translate(-0.2 * h, 0.2 * h);
+        assert!(close(moved.distance(&ramp), 0.));
+        moved.translate(-0.01, 0.01);
+        assert!(!moved.collides(&ramp));
+    }
+
+    #[test]
+    fn inertia_of_a_rect_and_a_disc_match_the_closed_forms() {
+        let (w, h, m) = (4., 2., 3.);
+        // Placed off the origin so the centroid, not the origin, has to be the axis.
+        let rect = Collidable::new_rect(0, 0, 5., -7., w, h);
+        let expected = m * (w * w + h * h) / 12.;
+        assert!(close(rect.moment_of_inertia(m), expected));
+        assert!(close(
+            clockwise(rect.clone()).moment_of_inertia(m),
+            expected
+        ));
+        assert!(close(
+            poly_inertia(rect.nvert, &rect.vertx, &rect.verty),
+            expected / m
+        ));
+
+        let r = 1.5;
+        let disc = Collidable::new_circle(0, 0, 2., 2., r);
+        assert!(close(disc.moment_of_inertia(m), m * r * r / 2.));
+        // A fine regular polygon comes close to the disc.
+        let poly = Collidable::new_regular(0, 0, 2., 2., r, 256, 0.);
+        let per_mass = poly_inertia(poly.nvert, &poly.vertx, &poly.verty);
+        assert!((per_mass - r * r / 2.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn raycast_exit_from_a_rects_centre_leaves_through_the_right_wall() {
+        let r = Collidable::new_rect(0, 0, 0., 0., 4., 2.);
+        let cases = [
+            ((1., 0.), 2., (4., 1.), (1., 0.)),
+            ((-1., 0.), 2., (0., 1.), (-1., 0.)),
+            ((0., 1.), 1., (2., 2.), (0., 1.)),
+            ((0., -3.), 1., (2., 0.), (0., -1.)),
+        ];
+        for &(ref shape, name) in &[(r.clone(), "ccw"), (clockwise(r.clone()), "cw")] {
+            for &((dx, dy), distance, point, normal) in &cases {
+                let hit = shape.raycast_exit(2., 1., dx, dy).expect(name);
+                assert!(close(hit.distance, distance), "{} {:?}", name, hit);
+                assert!(
+                    close2(hit.point, point) && close2(hit.normal, normal),
+                    "{} {:?}",
+                    name,
+                    hit
+                );
+            }
+        }
+        // Diagonally it leaves through the top wall first, the rect being wider than tall.
+        let hit = r.raycast_exit(2., 1., 1., 1.).unwrap();
+        assert!(close2(hit.point, (3., 2.)) && close2(hit.normal, (0., 1.)));
+        assert!(r.raycast_exit(5., 1., 1., 0.).is_none());
     }
 }
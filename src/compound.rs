@@ -0,0 +1,105 @@
+use aabb::Aabb;
+use collidable::Collidable;
+use Real;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A body made of several collidables, e.g. a concave shape built from convex pieces.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CompoundCollidable {
+    pub parts: Vec<Collidable>,
+    pub collidable_id: usize,
+}
+
+impl CompoundCollidable {
+    pub fn new(i: usize, parts: Vec<Collidable>) -> CompoundCollidable {
+        CompoundCollidable {
+            parts,
+            collidable_id: i,
+        }
+    }
+
+    /// True if any part collides with `other`.
+    pub fn collides(&self, other: &Collidable) -> bool {
+        self.parts.iter().any(|part| part.collides(other))
+    }
+
+    pub fn collides_compound(&self, other: &CompoundCollidable) -> bool {
+        other.parts.iter().any(|part| self.collides(part))
+    }
+
+    pub fn contains_point(&self, x: Real, y: Real) -> bool {
+        self.parts.iter().any(|part| part.contains_point(x, y))
+    }
+
+    pub fn translate(&mut self, dx: Real, dy: Real) {
+        for part in &mut self.parts {
+            part.translate(dx, dy);
+        }
+    }
+
+    /// The union of the parts' boxes as `(min_x, min_y, max_x, max_y)`. With no parts the box
+    /// is inverted, so it overlaps nothing.
+    pub fn aabb(&self) -> (Real, Real, Real, Real) {
+        let empty = Aabb::new(Real::MAX, Real::MAX, Real::MIN, Real::MIN);
+        let b = self
+            .parts
+            .iter()
+            .fold(empty, |acc, part| acc.merge(&Aabb::from(part.aabb())));
+        (b.min_x, b.min_y, b.max_x, b.max_y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An L over `[0, 2] x [0, 2]` with the top-right quarter missing.
+    fn l_body() -> CompoundCollidable {
+        CompoundCollidable::new(
+            9,
+            vec![
+                Collidable::new_rect(0, 9, 0., 0., 2., 1.),
+                Collidable::new_rect(0, 9, 0., 1., 1., 1.),
+            ],
+        )
+    }
+
+    #[test]
+    fn l_shape_collides_only_on_its_arms() {
+        let l = l_body();
+        assert!(l.collides(&Collidable::new_circle(0, 0, 1.7, 0.5, 0.2)));
+        assert!(l.collides(&Collidable::new_circle(0, 0, 0.5, 1.7, 0.2)));
+        assert!(l.collides(&Collidable::new_circle(0, 0, 0.5, 0.5, 0.2)));
+        // The notch is inside the bounding box but belongs to neither arm.
+        let notch = Collidable::new_circle(0, 0, 1.6, 1.6, 0.3);
+        assert!(!l.collides(&notch));
+        assert!(l.contains_point(0.5, 1.5) && !l.contains_point(1.5, 1.5));
+        assert_eq!(l.aabb(), (0., 0., 2., 2.));
+
+        // Reaching into the notch from above touches the vertical arm.
+        assert!(l.collides(&Collidable::new_rect(0, 0, 0.9, 1.2, 1., 0.5)));
+        assert!(!l.collides(&Collidable::new_rect(0, 0, 1.1, 1.1, 0.8, 0.8)));
+    }
+
+    #[test]
+    fn l_shapes_collide_as_compounds() {
+        let l = l_body();
+        // A second L turned into the first's notch without touching it.
+        let mut other = CompoundCollidable::new(
+            10,
+            vec![
+                Collidable::new_rect(0, 10, 1.2, 2.2, 2., 1.),
+                Collidable::new_rect(0, 10, 2.2, 1.2, 1., 1.),
+            ],
+        );
+        assert!(!l.collides_compound(&other) && !other.collides_compound(&l));
+        other.translate(-0.3, -0.3);
+        assert!(l.collides_compound(&other) && other.collides_compound(&l));
+
+        let empty = CompoundCollidable::new(11, vec![]);
+        assert!(!empty.collides_compound(&l) && !l.collides_compound(&empty));
+    }
+}
@@ -0,0 +1,162 @@
+use nalgebra::Vector2;
+
+pub use util::reflect;
+use util::{
+    poly_boundary_contains_pnt, poly_centroid, poly_contains_pnt, poly_signed_area,
+    segment_intersection,
+};
+use Real;
+
+/// Where the segments `a1`-`a2` and `b1`-`b2` meet, or `None` if they don't. When they
+/// overlap along a line, or one ends on the other, this is an endpoint lying on the other
+/// segment, preferring `b1`, `b2`, `a1` then `a2`.
+pub fn segment_intersect(
+    a1: (Real, Real),
+    a2: (Real, Real),
+    b1: (Real, Real),
+    b2: (Real, Real),
+) -> Option<(Real, Real)> {
+    let v = |p: (Real, Real)| Vector2::new(p.0, p.1);
+    segment_intersection(v(a1), v(a2), v(b1), v(b2)).map(|p| (p.x, p.y))
+}
+
+/// Crossing-number test for a point inside the polygon with vertices `vertx`/`verty`, in
+/// either winding. Points on the boundary count as inside, like `Collidable::contains_point`.
+pub fn point_in_polygon(x: Real, y: Real, vertx: &[Real], verty: &[Real]) -> bool {
+    let n = vertx.len().min(verty.len());
+    n > 0
+        && (poly_contains_pnt(n, vertx, verty, x, y)
+            || poly_boundary_contains_pnt(n, vertx, verty, x, y))
+}
+
+/// Shoelace area of the polygon, positive for counter-clockwise winding.
+pub fn shoelace_area(vertx: &[Real], verty: &[Real]) -> Real {
+    poly_signed_area(vertx.len().min(verty.len()), vertx, verty)
+}
+
+/// Area-weighted centroid of the polygon. Zero-area polygons give the mean of the vertices.
+pub fn polygon_centroid(vertx: &[Real], verty: &[Real]) -> (Real, Real) {
+    poly_centroid(vertx.len().min(verty.len()), vertx, verty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use consts;
+
+    #[test]
+    fn crossing_segments_meet_at_the_crossing() {
+        let p = segment_intersect((0., 0.), (2., 2.), (0., 2.), (2., 0.));
+        assert_eq!(p, Some((1., 1.)));
+        let p = segment_intersect((0., 0.), (4., 0.), (1., -1.), (1., 3.)).unwrap();
+        assert!((p.0 - 1.).abs() < 1e-6 && p.1.abs() < 1e-6);
+        assert_eq!(
+            segment_intersect((0., 0.), (2., 2.), (3., 0.), (2., 1.5)),
+            None
+        );
+    }
+
+    #[test]
+    fn parallel_segments_never_meet() {
+        assert_eq!(
+            segment_intersect((0., 0.), (2., 0.), (0., 1.), (2., 1.)),
+            None
+        );
+        assert_eq!(
+            segment_intersect((0., 0.), (1., 1.), (1., 0.), (2., 1.)),
+            None
+        );
+        // Collinear but apart.
+        assert_eq!(
+            segment_intersect((0., 0.), (1., 0.), (2., 0.), (3., 0.)),
+            None
+        );
+    }
+
+    #[test]
+    fn t_junction_meets_at_the_touching_endpoint() {
+        assert_eq!(
+            segment_intersect((0., 0.), (2., 0.), (1., 0.), (1., 1.)),
+            Some((1., 0.))
+        );
+        assert_eq!(
+            segment_intersect((1., 1.), (1., 0.), (0., 0.), (2., 0.)),
+            Some((1., 0.))
+        );
+        // Touching end to end.
+        assert_eq!(
+            segment_intersect((0., 0.), (1., 0.), (1., 0.), (1., 5.)),
+            Some((1., 0.))
+        );
+    }
+
+    #[test]
+    fn collinear_overlap_gives_an_overlap_endpoint() {
+        assert_eq!(
+            segment_intersect((0., 0.), (2., 0.), (1., 0.), (3., 0.)),
+            Some((1., 0.))
+        );
+        assert_eq!(
+            segment_intersect((0., 0.), (2., 0.), (3., 0.), (1., 0.)),
+            Some((1., 0.))
+        );
+        assert_eq!(
+            segment_intersect((1., 0.), (3., 0.), (0., 0.), (2., 0.)),
+            Some((2., 0.))
+        );
+    }
+
+    const SQUARE: ([Real; 4], [Real; 4]) = ([0., 1., 1., 0.], [0., 0., 1., 1.]);
+    const TRIANGLE: ([Real; 3], [Real; 3]) = ([0., 4., 0.], [0., 0., 3.]);
+
+    #[test]
+    fn point_in_unit_square_and_triangle() {
+        let (sx, sy) = SQUARE;
+        assert!(point_in_polygon(0.5, 0.5, &sx, &sy));
+        assert!(point_in_polygon(1., 0.5, &sx, &sy) && point_in_polygon(0., 0., &sx, &sy));
+        assert!(!point_in_polygon(1.5, 0.5, &sx, &sy) && !point_in_polygon(0.5, -0.1, &sx, &sy));
+        let (tx, ty) = TRIANGLE;
+        assert!(point_in_polygon(1., 1., &tx, &ty));
+        assert!(point_in_polygon(2., 1.5, &tx, &ty));
+        assert!(!point_in_polygon(3., 2., &tx, &ty));
+        let (rx, ry): (Vec<Real>, Vec<Real>) = (
+            tx.iter().rev().cloned().collect(),
+            ty.iter().rev().cloned().collect(),
+        );
+        assert!(point_in_polygon(1., 1., &rx, &ry) && !point_in_polygon(3., 2., &rx, &ry));
+        assert!(!point_in_polygon(0., 0., &[], &[]));
+    }
+
+    #[test]
+    fn shoelace_area_is_signed_by_winding() {
+        let (sx, sy) = SQUARE;
+        assert_eq!(shoelace_area(&sx, &sy), 1.);
+        let (tx, ty) = TRIANGLE;
+        assert_eq!(shoelace_area(&tx, &ty), 6.);
+        let (rx, ry): (Vec<Real>, Vec<Real>) = (
+            tx.iter().rev().cloned().collect(),
+            ty.iter().rev().cloned().collect(),
+        );
+        assert_eq!(shoelace_area(&rx, &ry), -6.);
+    }
+
+    #[test]
+    fn centroid_of_unit_square_and_triangle() {
+        let (sx, sy) = SQUARE;
+        assert_eq!(polygon_centroid(&sx, &sy), (0.5, 0.5));
+        let (tx, ty) = TRIANGLE;
+        let (cx, cy) = polygon_centroid(&tx, &ty);
+        assert!((cx - 4. / 3.).abs() < 1e-6 && (cy - 1.).abs() < 1e-6);
+        assert_eq!(polygon_centroid(&[0., 2., 4.], &[0., 0., 0.]), (2., 0.));
+    }
+
+    #[test]
+    fn reflect_off_a_floor_flips_y() {
+        assert_eq!(reflect(3., -2., 0., 1.), (3., 2.));
+        assert_eq!(reflect(3., -2., 0., -1.), (3., 2.));
+        assert_eq!(reflect(-4., 0., 1., 0.), (4., 0.));
+        let h = consts::FRAC_1_SQRT_2;
+        let (x, y) = reflect(1., 0., -h, h);
+        assert!(x.abs() < 1e-6 && (y - 1.).abs() < 1e-6);
+    }
+}
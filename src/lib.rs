@@ -1,5 +1,31 @@
+//! 2d collision detection.
+//!
+//! NOTE: There is no `no_std` build. nalgebra 0.10 requires `std`, the float maths (`sqrt`,
+//! `sin`, `atan2`, ...) comes from `std` rather than `core`, and `SpatialHash` and
+//! `CollisionWorld` are built on `std::collections`. Supporting it would mean moving to a
+//! newer nalgebra and a `libm` dependency.
+
 extern crate nalgebra;
+#[cfg(feature = "serde")]
+extern crate serde;
 
+pub mod aabb;
+pub mod aabb_quadtree;
 pub mod collidable;
+pub mod compound;
+pub mod geometry;
 pub mod quadtree;
+pub mod spatial_hash;
 mod util;
+pub mod world;
+
+/// The float type used for all coordinates. `f32` unless the `f64` feature is enabled.
+#[cfg(not(feature = "f64"))]
+pub type Real = f32;
+#[cfg(feature = "f64")]
+pub type Real = f64;
+
+#[cfg(not(feature = "f64"))]
+use std::f32::consts;
+#[cfg(feature = "f64")]
+use std::f64::consts;
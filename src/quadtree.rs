@@ -4,18 +4,19 @@ use nalgebra::{Norm, Vector2};
 
 use collidable::{Collidable, CollidableShape};
 use util::*;
+use Real;
 
 pub type Collision = (Collidable, Collidable, FaceIndex, SupportPoint);
 
 pub struct Quadtree {
-    pub vertx: [f32; 4],
-    pub verty: [f32; 4],
+    pub vertx: [Real; 4],
+    pub verty: [Real; 4],
     pub quads: Vec<Quadtree>,
     pub items: Vec<Collidable>,
 }
 
 impl Quadtree {
-    pub fn new(vertx: &[f32], verty: &[f32], depth: usize) -> Quadtree {
+    pub fn new(vertx: &[Real], verty: &[Real], depth: usize) -> Quadtree {
         let mut quads: Vec<Quadtree> = vec![];
         if depth > 0 {
             let mut subvert = [0.; 32];
@@ -95,7 +96,7 @@ impl Quadtree {
                         return;
                     }
                 }
-            } else {
+            } else if item.collidable_shape as u8 == CollidableShape::Polygon as u8 {
                 for i in 0..4 {
                     if poly_contains_poly(
                         4,
@@ -109,6 +110,16 @@ impl Quadtree {
                         return;
                     }
                 }
+            } else {
+                let (minx, miny, maxx, maxy) = item.aabb();
+                for i in 0..4 {
+                    let (qminx, qminy, qmaxx, qmaxy) =
+                        poly_extents(4, &self.quads[i].vertx, &self.quads[i].verty);
+                    if minx > qminx && miny > qminy && maxx < qmaxx && maxy < qmaxy {
+                        self.quads[i].insert(item);
+                        return;
+                    }
+                }
             }
         }
         self.items.push(item);
@@ -116,7 +127,7 @@ impl Quadtree {
 
     pub fn check_collisions(
         &self,
-        collisions: &mut Vec<(Collidable, Collidable, usize, Vector2<f32>)>,
+        collisions: &mut Vec<(Collidable, Collidable, usize, Vector2<Real>)>,
     ) -> Vec<&Collidable> {
         let mut all_items = vec![];
         if !self.quads.is_empty() {
@@ -171,6 +182,9 @@ pub fn possible_collision_between(a: &Collidable, b: &Collidable, collisions: &m
                     }
                 }
             }
+            CollidableShape::Capsule | CollidableShape::Segment | CollidableShape::Ellipse => {
+                faceless_collision_between(a, b, collisions)
+            }
         },
         CollidableShape::Polygon => match b.collidable_shape {
             CollidableShape::Circle => {
@@ -208,7 +222,22 @@ pub fn possible_collision_between(a: &Collidable, b: &Collidable, collisions: &m
                     }
                 }
             }
+            CollidableShape::Capsule | CollidableShape::Segment | CollidableShape::Ellipse => {
+                faceless_collision_between(a, b, collisions)
+            }
         },
+        CollidableShape::Capsule | CollidableShape::Segment | CollidableShape::Ellipse => {
+            faceless_collision_between(a, b, collisions)
+        }
+    }
+}
+
+/// Capsules, segments and ellipses have no faces, so they report face 0 and the point on `a`
+/// nearest `b`.
+fn faceless_collision_between(a: &Collidable, b: &Collidable, collisions: &mut Vec<Collision>) {
+    if a.collides(b) {
+        let (s, _) = a.closest_core_points(b);
+        collisions.push((a.clone(), b.clone(), 0, s));
     }
 }
 // CollidableShape::Arc =>
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use collidable::Collidable;
+use Real;
+
+type Cell = (i64, i64);
+
+/// A uniform grid broadphase. Each collidable is stored in every cell its bounding box
+/// touches, keyed by `collidable_id`.
+#[derive(Clone, Debug)]
+pub struct SpatialHash {
+    pub cell_size: Real,
+    cells: HashMap<Cell, Vec<usize>>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: Real) -> SpatialHash {
+        assert!(cell_size > 0., "cell size must be positive");
+        SpatialHash {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, x: Real, y: Real) -> Cell {
+        (
+            (x / self.cell_size).floor() as i64,
+            (y / self.cell_size).floor() as i64,
+        )
+    }
+
+    fn cells_in(&self, min_x: Real, min_y: Real, max_x: Real, max_y: Real) -> Vec<Cell> {
+        let (x0, y0) = self.cell(min_x, min_y);
+        let (x1, y1) = self.cell(max_x, max_y);
+        let mut cells = vec![];
+        for x in x0..x1 + 1 {
+            for y in y0..y1 + 1 {
+                cells.push((x, y));
+            }
+        }
+        cells
+    }
+
+    pub fn insert(&mut self, collidable: &Collidable) {
+        let (min_x, min_y, max_x, max_y) = collidable.aabb();
+        for cell in self.cells_in(min_x, min_y, max_x, max_y) {
+            self.cells
+                .entry(cell)
+                .or_default()
+                .push(collidable.collidable_id);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    /// Ids of the collidables sharing a cell with the region, in ascending order. These are
+    /// only candidates, they still need a narrowphase check.
+    pub fn query_region(&self, min_x: Real, min_y: Real, max_x: Real, max_y: Real) -> Vec<usize> {
+        let mut ids = vec![];
+        for cell in self.cells_in(min_x, min_y, max_x, max_y) {
+            if let Some(bucket) = self.cells.get(&cell) {
+                ids.extend_from_slice(bucket);
+            }
+        }
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    /// Every pair of ids sharing at least one cell, lowest id first.
+    pub fn potential_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = vec![];
+        for bucket in self.cells.values() {
+            for (i, &a) in bucket.iter().enumerate() {
+                for &b in &bucket[i + 1..] {
+                    if a != b {
+                        pairs.push((a.min(b), a.max(b)));
+                    }
+                }
+            }
+        }
+        pairs.sort();
+        pairs.dedup();
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> Real {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (self.0 >> 40) as Real / (1u64 << 24) as Real
+        }
+
+        fn range(&mut self, lo: Real, hi: Real) -> Real {
+            lo + (hi - lo) * self.next()
+        }
+    }
+
+    fn overlapping(a: &Collidable, b: &Collidable) -> bool {
+        let (a0, a1, a2, a3) = a.aabb();
+        let (b0, b1, b2, b3) = b.aabb();
+        a0 <= b2 && b0 <= a2 && a1 <= b3 && b1 <= a3
+    }
+
+    /// Circles scattered over a 1000 x 1000 square, or packed into a 100 x 100 corner of it.
+    fn scatter(n: usize, clustered: bool, seed: u64) -> Vec<Collidable> {
+        let mut rng = Lcg(seed);
+        let extent = if clustered { 100. } else { 1000. };
+        (0..n)
+            .map(|i| {
+                let (x, y) = (rng.range(0., extent), rng.range(0., extent));
+                Collidable::new_circle(0, i, x, y, rng.range(1., 6.))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn an_object_spanning_cells_is_reported_once() {
+        let mut hash = SpatialHash::new(10.);
+        let big = Collidable::new_rect(0, 7, 1., 1., 28., 28.);
+        let left = Collidable::new_circle(0, 1, 3., 3., 1.);
+        let right = Collidable::new_circle(0, 2, 26., 26., 1.);
+        for c in &[&big, &left, &right] {
+            hash.insert(c);
+        }
+        assert_eq!(hash.query_region(0., 0., 30., 30.), vec![1, 2, 7]);
+        assert_eq!(hash.query_region(12., 12., 18., 18.), vec![7]);
+        assert_eq!(hash.query_region(40., 40., 50., 50.), Vec::<usize>::new());
+        // The big rect shares a cell with each circle, but the circles share none.
+        assert_eq!(hash.potential_pairs(), vec![(1, 7), (2, 7)]);
+
+        hash.clear();
+        assert!(hash.potential_pairs().is_empty());
+    }
+
+    #[test]
+    fn clustered_and_uniform_scenes_keep_every_overlap() {
+        let n = 2000;
+        let mut counts = vec![];
+        for &clustered in &[false, true] {
+            let shapes = scatter(n, clustered, 5);
+            let mut hash = SpatialHash::new(16.);
+            for c in &shapes {
+                hash.insert(c);
+            }
+            let pairs = hash.potential_pairs();
+            assert!(pairs.windows(2).all(|w| w[0] < w[1]));
+            for i in 0..n {
+                for j in i + 1..n {
+                    if overlapping(&shapes[i], &shapes[j]) {
+                        assert!(
+                            pairs.binary_search(&(i, j)).is_ok(),
+                            "missed ({}, {})",
+                            i,
+                            j
+                        );
+                    }
+                }
+            }
+
+            let (x, y) = (shapes[0].centrex, shapes[0].centrey);
+            let query = Collidable::new_rect(0, n, x - 20., y - 20., 40., 40.);
+            let (min_x, min_y, max_x, max_y) = query.aabb();
+            let ids = hash.query_region(min_x, min_y, max_x, max_y);
+            for c in shapes.iter().filter(|c| overlapping(c, &query)) {
+                assert!(ids.binary_search(&c.collidable_id).is_ok());
+            }
+            counts.push(pairs.len());
+        }
+        // Packing the same objects into a hundredth of the area crowds the cells, so the
+        // broadphase hands on far more candidates.
+        assert!(counts[1] > counts[0] * 10, "{:?}", counts);
+    }
+}
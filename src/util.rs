@@ -1,13 +1,23 @@
 extern crate nalgebra;
 
+use consts;
 use nalgebra::Vector2;
-use std::f32;
+use std::cmp::Ordering::Equal;
+use Real;
 
-pub type Distance = f32;
+pub type Distance = Real;
 pub type FaceIndex = usize;
-pub type SupportPoint = Vector2<f32>;
+pub type SupportPoint = Vector2<Real>;
 
-pub fn poly_contains_pnt(nvert: usize, vertx: &[f32], verty: &[f32], pntx: f32, pnty: f32) -> bool {
+/// Crossing-number point-in-polygon test, independent of winding. Points on the boundary
+/// may land either way; pair it with `poly_boundary_contains_pnt` for an inclusive test.
+pub fn poly_contains_pnt(
+    nvert: usize,
+    vertx: &[Real],
+    verty: &[Real],
+    pntx: Real,
+    pnty: Real,
+) -> bool {
     let mut j = nvert - 1;
     let mut c = false;
     for i in 0..nvert {
@@ -21,13 +31,38 @@ pub fn poly_contains_pnt(nvert: usize, vertx: &[f32], verty: &[f32], pntx: f32,
     return c;
 }
 
+/// True if the point lies exactly on one of the polygon's edges.
+pub fn poly_boundary_contains_pnt(
+    nvert: usize,
+    vertx: &[Real],
+    verty: &[Real],
+    pntx: Real,
+    pnty: Real,
+) -> bool {
+    let mut j = nvert - 1;
+    for i in 0..nvert {
+        let cross =
+            (vertx[i] - vertx[j]) * (pnty - verty[j]) - (verty[i] - verty[j]) * (pntx - vertx[j]);
+        if cross == 0.
+            && pntx >= vertx[i].min(vertx[j])
+            && pntx <= vertx[i].max(vertx[j])
+            && pnty >= verty[i].min(verty[j])
+            && pnty <= verty[i].max(verty[j])
+        {
+            return true;
+        }
+        j = i;
+    }
+    false
+}
+
 pub fn poly_contains_poly(
     na: usize,
-    ax: &[f32],
-    ay: &[f32],
+    ax: &[Real],
+    ay: &[Real],
     nb: usize,
-    bx: &[f32],
-    by: &[f32],
+    bx: &[Real],
+    by: &[Real],
 ) -> bool {
     let mut c = true;
     for i in 0..nb {
@@ -38,18 +73,18 @@ pub fn poly_contains_poly(
     return c;
 }
 
-pub fn sqrf(x: f32) -> f32 {
+pub fn sqrf(x: Real) -> Real {
     x * x
 }
 
-pub fn dist_sqrdf(vx: f32, vy: f32, wx: f32, wy: f32) -> f32 {
+pub fn dist_sqrdf(vx: Real, vy: Real, wx: Real, wy: Real) -> Real {
     sqrf(vx - wx) + sqrf(vy - wy)
 }
 
-pub fn dist_line_pnt(lax: f32, lay: f32, lbx: f32, lby: f32, px: f32, py: f32) -> Distance {
+pub fn dist_line_pnt(lax: Real, lay: Real, lbx: Real, lby: Real, px: Real, py: Real) -> Distance {
     let length_sqr = dist_sqrdf(lax, lay, lbx, lby);
     if length_sqr == 0. {
-        return dist_sqrdf(px, py, lax, lay);
+        return dist_sqrdf(px, py, lax, lay).sqrt();
     }
     let t = ((px - lax) * (lbx - lax) + (py - lay) * (lby - lay)) / length_sqr;
     if t < 0. {
@@ -64,19 +99,19 @@ pub fn dist_line_pnt(lax: f32, lay: f32, lbx: f32, lby: f32, px: f32, py: f32) -
 }
 
 pub fn dist_line_pnt_with_supp(
-    lax: f32,
-    lay: f32,
-    lbx: f32,
-    lby: f32,
-    px: f32,
-    py: f32,
+    lax: Real,
+    lay: Real,
+    lbx: Real,
+    lby: Real,
+    px: Real,
+    py: Real,
 ) -> (Distance, SupportPoint) {
     let mut closest_x = px;
     let mut closest_y = py;
     let length_sqr = dist_sqrdf(lax, lay, lbx, lby);
     if length_sqr == 0. {
         return (
-            dist_sqrdf(px, py, lax, lay),
+            dist_sqrdf(px, py, lax, lay).sqrt(),
             SupportPoint::new(closest_x, closest_y),
         );
     }
@@ -109,13 +144,13 @@ pub fn dist_line_pnt_with_supp(
 
 pub fn dist_poly_circ(
     nvert: usize,
-    vertx: &[f32],
-    verty: &[f32],
-    circx: f32,
-    circy: f32,
+    vertx: &[Real],
+    verty: &[Real],
+    circx: Real,
+    circy: Real,
 ) -> Distance {
-    let mut distance = f32::MAX;
-    let mut temp: f32;
+    let mut distance = Real::MAX;
+    let mut temp: Real;
     for i in 0..nvert - 1 {
         temp = dist_line_pnt(vertx[i], verty[i], vertx[i + 1], verty[i + 1], circx, circy);
         if temp < distance {
@@ -138,19 +173,19 @@ pub fn dist_poly_circ(
 
 pub fn dist_poly_circ_with_face_and_supp(
     nvert: usize,
-    vertx: &[f32],
-    verty: &[f32],
-    circx: f32,
-    circy: f32,
+    vertx: &[Real],
+    verty: &[Real],
+    circx: Real,
+    circy: Real,
 ) -> (Distance, FaceIndex, SupportPoint) {
     let mut closest_x = 0.;
     let mut closest_y = 0.;
     let mut face_index = 0;
-    let mut distance = f32::MAX;
-    let mut temp: f32;
+    let mut distance = Real::MAX;
+    let mut temp: Real;
     for i in 0..nvert - 1 {
-        let cx: f32;
-        let cy: f32;
+        let cx: Real;
+        let cy: Real;
         temp = match dist_line_pnt_with_supp(
             vertx[i],
             verty[i],
@@ -172,8 +207,8 @@ pub fn dist_poly_circ_with_face_and_supp(
             face_index = i;
         }
     }
-    let cx: f32;
-    let cy: f32;
+    let cx: Real;
+    let cy: Real;
     temp = match dist_line_pnt_with_supp(
         vertx[nvert - 1],
         verty[nvert - 1],
@@ -201,11 +236,20 @@ pub fn dist_poly_circ_with_face_and_supp(
     )
 }
 
-pub fn dist_circ_circ(ax: f32, ay: f32, bx: f32, by: f32) -> Distance {
+pub fn dist_circ_circ(ax: Real, ay: Real, bx: Real, by: Real) -> Distance {
     ((ax - bx) * (ax - bx) + (ay - by) * (ay - by)).sqrt()
 }
 
-pub fn calc_normx(nvert: usize, verty: &[f32]) -> Vec<f32> {
+/// x components of the edge normals: `normx[i]` belongs to the edge from vertex `i` to
+/// `i + 1`, wrapping round. Together with `calc_normy` the normal of an edge `(dx, dy)` is
+/// `(dy, -dx)`, which points outward for counter-clockwise vertices and inward for clockwise.
+///
+/// NOTE: Normals aren't normalized; each is as long as its edge. A zero-length edge from a
+/// repeated vertex gets a zero normal, never NaN, and the SAT loops skip those.
+pub fn calc_normx(nvert: usize, verty: &[Real]) -> Vec<Real> {
+    if nvert == 0 {
+        return vec![];
+    }
     let mut normx = vec![0.; nvert];
     for i in 0..nvert - 1 {
         normx[i] = verty[i + 1] - verty[i];
@@ -214,7 +258,11 @@ pub fn calc_normx(nvert: usize, verty: &[f32]) -> Vec<f32> {
     normx
 }
 
-pub fn calc_normy(nvert: usize, vertx: &[f32]) -> Vec<f32> {
+/// y components of the edge normals; see `calc_normx`.
+pub fn calc_normy(nvert: usize, vertx: &[Real]) -> Vec<Real> {
+    if nvert == 0 {
+        return vec![];
+    }
     let mut normy = vec![0.; nvert];
     for i in 0..nvert - 1 {
         normy[i] = -vertx[i + 1] + vertx[i];
@@ -223,8 +271,13 @@ pub fn calc_normy(nvert: usize, vertx: &[f32]) -> Vec<f32> {
     normy
 }
 
-pub fn support_pnt(nvert: usize, vertx: &[f32], verty: &[f32], dir: Vector2<f32>) -> SupportPoint {
-    let mut best_proj = f32::MIN;
+pub fn support_pnt(
+    nvert: usize,
+    vertx: &[Real],
+    verty: &[Real],
+    dir: Vector2<Real>,
+) -> SupportPoint {
+    let mut best_proj = Real::MIN;
     let mut best_vert = nalgebra::zero();
     for i in 0..nvert {
         let vert = Vector2::new(vertx[i], verty[i]);
@@ -239,15 +292,15 @@ pub fn support_pnt(nvert: usize, vertx: &[f32], verty: &[f32], dir: Vector2<f32>
 
 pub fn penetration(
     nverta: usize,
-    vertxa: &[f32],
-    vertya: &[f32],
-    normxa: &[f32],
-    normya: &[f32],
+    vertxa: &[Real],
+    vertya: &[Real],
+    normxa: &[Real],
+    normya: &[Real],
     nvertb: usize,
-    vertxb: &[f32],
-    vertyb: &[f32],
+    vertxb: &[Real],
+    vertyb: &[Real],
 ) -> (Distance, FaceIndex, SupportPoint) {
-    let mut best_dist = f32::MIN;
+    let mut best_dist = Real::MIN;
     let mut best_face = 0;
     let mut best_supp = nalgebra::zero();
     for i in 0..nverta {
@@ -263,3 +316,696 @@ pub fn penetration(
     }
     (best_dist, best_face, best_supp)
 }
+
+pub fn project_poly(
+    nvert: usize,
+    vertx: &[Real],
+    verty: &[Real],
+    axisx: Real,
+    axisy: Real,
+) -> (Real, Real) {
+    let mut min = Real::MAX;
+    let mut max = Real::MIN;
+    for i in 0..nvert {
+        let p = vertx[i] * axisx + verty[i] * axisy;
+        if p < min {
+            min = p;
+        }
+        if p > max {
+            max = p;
+        }
+    }
+    (min, max)
+}
+
+pub fn poly_extents(nvert: usize, vertx: &[Real], verty: &[Real]) -> (Real, Real, Real, Real) {
+    let mut minx = Real::MAX;
+    let mut miny = Real::MAX;
+    let mut maxx = Real::MIN;
+    let mut maxy = Real::MIN;
+    for i in 0..nvert {
+        minx = minx.min(vertx[i]);
+        miny = miny.min(verty[i]);
+        maxx = maxx.max(vertx[i]);
+        maxy = maxy.max(verty[i]);
+    }
+    (minx, miny, maxx, maxy)
+}
+
+/// Shoelace area, positive for counter-clockwise winding.
+pub fn poly_signed_area(nvert: usize, vertx: &[Real], verty: &[Real]) -> Real {
+    let mut area = 0.;
+    for i in 0..nvert {
+        let j = (i + 1) % nvert;
+        area += vertx[i] * verty[j] - vertx[j] * verty[i];
+    }
+    area * 0.5
+}
+
+/// Area-weighted centroid. Zero-area polygons fall back to the mean of the vertices.
+pub fn poly_centroid(nvert: usize, vertx: &[Real], verty: &[Real]) -> (Real, Real) {
+    let area = poly_signed_area(nvert, vertx, verty);
+    if area == 0. {
+        let n = nvert.max(1) as Real;
+        return (
+            vertx.iter().take(nvert).sum::<Real>() / n,
+            verty.iter().take(nvert).sum::<Real>() / n,
+        );
+    }
+    let (mut cx, mut cy) = (0., 0.);
+    for i in 0..nvert {
+        let j = (i + 1) % nvert;
+        let cross = vertx[i] * verty[j] - vertx[j] * verty[i];
+        cx += (vertx[i] + vertx[j]) * cross;
+        cy += (verty[i] + verty[j]) * cross;
+    }
+    (cx / (6. * area), cy / (6. * area))
+}
+
+/// Moment of inertia per unit mass about the centroid, for a uniform density polygon of
+/// either winding. Zero-area polygons give 0.
+pub fn poly_inertia(nvert: usize, vertx: &[Real], verty: &[Real]) -> Real {
+    let (cx, cy) = poly_centroid(nvert, vertx, verty);
+    let (mut num, mut den) = (0., 0.);
+    for i in 0..nvert {
+        let j = (i + 1) % nvert;
+        let (ax, ay) = (vertx[i] - cx, verty[i] - cy);
+        let (bx, by) = (vertx[j] - cx, verty[j] - cy);
+        let cross = ax * by - bx * ay;
+        num += cross * (ax * ax + ay * ay + ax * bx + ay * by + bx * bx + by * by);
+        den += cross;
+    }
+    if den == 0. {
+        0.
+    } else {
+        num / (6. * den)
+    }
+}
+
+/// True if every turn goes the same way. Collinear vertices are ignored, and a polygon whose
+/// turns wind round more than once (like a pentagram) isn't convex.
+pub fn poly_is_convex(nvert: usize, vertx: &[Real], verty: &[Real]) -> bool {
+    let mut sign: Real = 0.;
+    let mut turning: Real = 0.;
+    for i in 0..nvert {
+        let j = (i + 1) % nvert;
+        let k = (i + 2) % nvert;
+        let (ex, ey) = (vertx[j] - vertx[i], verty[j] - verty[i]);
+        let (fx, fy) = (vertx[k] - vertx[j], verty[k] - verty[j]);
+        let cross = ex * fy - ey * fx;
+        if cross != 0. {
+            if sign * cross < 0. {
+                return false;
+            }
+            sign = cross;
+        }
+        turning += cross.atan2(ex * fx + ey * fy);
+    }
+    turning.abs() < 3. * consts::PI
+}
+
+fn clamp01(x: Real) -> Real {
+    x.clamp(0., 1.)
+}
+
+/// Closest points between segments `a1 a2` and `b1 b2`, either of which may be degenerate.
+/// Parallel segments pick the pair nearest `a1`.
+pub fn closest_pnts_seg_seg(
+    a1: SupportPoint,
+    a2: SupportPoint,
+    b1: SupportPoint,
+    b2: SupportPoint,
+) -> (SupportPoint, SupportPoint) {
+    let da = a2 - a1;
+    let db = b2 - b1;
+    let r = a1 - b1;
+    let a = nalgebra::dot(&da, &da);
+    let e = nalgebra::dot(&db, &db);
+    let f = nalgebra::dot(&db, &r);
+    let (s, t);
+    if a == 0. && e == 0. {
+        s = 0.;
+        t = 0.;
+    } else if a == 0. {
+        s = 0.;
+        t = clamp01(f / e);
+    } else {
+        let c = nalgebra::dot(&da, &r);
+        if e == 0. {
+            s = clamp01(-c / a);
+            t = 0.;
+        } else {
+            let b = nalgebra::dot(&da, &db);
+            let denom = a * e - b * b;
+            let s0 = if denom != 0. {
+                clamp01((b * f - c * e) / denom)
+            } else {
+                0.
+            };
+            let t0 = (b * s0 + f) / e;
+            if t0 < 0. {
+                s = clamp01(-c / a);
+                t = 0.;
+            } else if t0 > 1. {
+                s = clamp01((b - c) / a);
+                t = 1.;
+            } else {
+                s = s0;
+                t = t0;
+            }
+        }
+    }
+    (a1 + da * s, b1 + db * t)
+}
+
+fn orientation(a: SupportPoint, b: SupportPoint, c: SupportPoint) -> Real {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+fn on_segment(a: SupportPoint, b: SupportPoint, p: SupportPoint) -> bool {
+    p.x >= a.x.min(b.x) && p.x <= a.x.max(b.x) && p.y >= a.y.min(b.y) && p.y <= a.y.max(b.y)
+}
+
+/// True if the segments share any point, including collinear overlap and endpoint contact.
+pub fn segments_intersect(
+    a1: SupportPoint,
+    a2: SupportPoint,
+    b1: SupportPoint,
+    b2: SupportPoint,
+) -> bool {
+    segment_intersection(a1, a2, b1, b2).is_some()
+}
+
+/// Where the segments meet. When they overlap along a line, or one ends on the other, this
+/// is an endpoint lying on the other segment, preferring `b1`, `b2`, `a1` then `a2`.
+pub fn segment_intersection(
+    a1: SupportPoint,
+    a2: SupportPoint,
+    b1: SupportPoint,
+    b2: SupportPoint,
+) -> Option<SupportPoint> {
+    let d1 = orientation(b1, b2, a1);
+    let d2 = orientation(b1, b2, a2);
+    let d3 = orientation(a1, a2, b1);
+    let d4 = orientation(a1, a2, b2);
+    if ((d1 > 0. && d2 < 0.) || (d1 < 0. && d2 > 0.))
+        && ((d3 > 0. && d4 < 0.) || (d3 < 0. && d4 > 0.))
+    {
+        return Some(a1 + (a2 - a1) * (d1 / (d1 - d2)));
+    }
+    if d3 == 0. && on_segment(a1, a2, b1) {
+        Some(b1)
+    } else if d4 == 0. && on_segment(a1, a2, b2) {
+        Some(b2)
+    } else if d1 == 0. && on_segment(b1, b2, a1) {
+        Some(a1)
+    } else if d2 == 0. && on_segment(b1, b2, a2) {
+        Some(a2)
+    } else {
+        None
+    }
+}
+
+fn rdp(vertx: &[Real], verty: &[Real], chain: &[usize], tolerance: Real, keep: &mut Vec<usize>) {
+    let (a, b) = (chain[0], chain[chain.len() - 1]);
+    let mut far = (0., 0);
+    for (k, &i) in chain.iter().enumerate().take(chain.len() - 1).skip(1) {
+        let d = dist_line_pnt(vertx[a], verty[a], vertx[b], verty[b], vertx[i], verty[i]);
+        if d > far.0 {
+            far = (d, k);
+        }
+    }
+    if far.0 > tolerance {
+        rdp(vertx, verty, &chain[..far.1 + 1], tolerance, keep);
+        rdp(vertx, verty, &chain[far.1..], tolerance, keep);
+    } else {
+        keep.push(a);
+    }
+}
+
+/// Ramer-Douglas-Peucker over a closed ring, returning the indices kept in order. The ring is
+/// split at its two farthest-apart vertices so the seam can't collapse it to a line.
+pub fn simplify_rdp_ring(
+    nvert: usize,
+    vertx: &[Real],
+    verty: &[Real],
+    tolerance: Real,
+) -> Vec<usize> {
+    if nvert < 4 {
+        return (0..nvert).collect();
+    }
+    let mut split = (0., 0, 0);
+    for i in 0..nvert {
+        for j in i + 1..nvert {
+            let d = dist_sqrdf(vertx[i], verty[i], vertx[j], verty[j]);
+            if d > split.0 {
+                split = (d, i, j);
+            }
+        }
+    }
+    let (i, j) = (split.1, split.2);
+    let first: Vec<usize> = (i..j + 1).collect();
+    let second: Vec<usize> = (j..nvert).chain(0..i + 1).collect();
+    let mut keep = vec![];
+    rdp(vertx, verty, &first, tolerance, &mut keep);
+    rdp(vertx, verty, &second, tolerance, &mut keep);
+    keep
+}
+
+/// Sutherland-Hodgman: the part of the subject polygon inside the convex, counter-clockwise
+/// clip polygon.
+pub fn clip_poly(
+    subjx: &[Real],
+    subjy: &[Real],
+    clipx: &[Real],
+    clipy: &[Real],
+) -> (Vec<Real>, Vec<Real>) {
+    let mut outx = subjx.to_vec();
+    let mut outy = subjy.to_vec();
+    let n = clipx.len();
+    for i in 0..n {
+        if outx.is_empty() {
+            break;
+        }
+        let a = SupportPoint::new(clipx[i], clipy[i]);
+        let b = SupportPoint::new(clipx[(i + 1) % n], clipy[(i + 1) % n]);
+        let (inx, iny) = (outx, outy);
+        outx = vec![];
+        outy = vec![];
+        let m = inx.len();
+        for j in 0..m {
+            let p = SupportPoint::new(inx[j], iny[j]);
+            let q = SupportPoint::new(inx[(j + 1) % m], iny[(j + 1) % m]);
+            let (dp, dq) = (orientation(a, b, p), orientation(a, b, q));
+            if dp >= 0. {
+                outx.push(p.x);
+                outy.push(p.y);
+            }
+            if (dp > 0. && dq < 0.) || (dp < 0. && dq > 0.) {
+                let x = p + (q - p) * (dp / (dp - dq));
+                outx.push(x.x);
+                outy.push(x.y);
+            }
+        }
+    }
+    (outx, outy)
+}
+
+/// Andrew's monotone chain. The hull is counter-clockwise without repeated or collinear
+/// points; fewer than 3 points come back when the input is degenerate.
+pub fn convex_hull(points: &[(Real, Real)]) -> Vec<SupportPoint> {
+    let mut pts: Vec<SupportPoint> = points
+        .iter()
+        .map(|&(x, y)| SupportPoint::new(x, y))
+        .collect();
+    pts.sort_by(|a, b| {
+        a.x.partial_cmp(&b.x)
+            .unwrap_or(Equal)
+            .then(a.y.partial_cmp(&b.y).unwrap_or(Equal))
+    });
+    pts.dedup();
+    if pts.len() < 3 {
+        return pts;
+    }
+    let mut hull: Vec<SupportPoint> = Vec::with_capacity(pts.len() * 2);
+    for pass in 0..2 {
+        let start = hull.len();
+        for k in 0..pts.len() {
+            let p = if pass == 0 {
+                pts[k]
+            } else {
+                pts[pts.len() - 1 - k]
+            };
+            while hull.len() >= start + 2
+                && orientation(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        // Each chain's last point starts the other.
+        hull.pop();
+    }
+    hull
+}
+
+/// True if no two edges meet except adjacent edges at their shared vertex. An edge that
+/// doubles back along the previous one counts as an intersection.
+pub fn poly_is_simple(nvert: usize, vertx: &[Real], verty: &[Real]) -> bool {
+    let edge = |i: usize| (vert(vertx, verty, i), vert(vertx, verty, (i + 1) % nvert));
+    for i in 0..nvert {
+        let (a1, a2) = edge(i);
+        let (b1, b2) = edge((i + 1) % nvert);
+        let (e, f) = (a2 - a1, b2 - b1);
+        if e.x * f.y - e.y * f.x == 0. && e.x * f.x + e.y * f.y < 0. {
+            return false;
+        }
+        // Skip the edge before `i`, which shares vertex `i`.
+        for j in i + 2..nvert - (i == 0) as usize {
+            let (b1, b2) = edge(j);
+            if segments_intersect(a1, a2, b1, b2) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// True if the segments cross at a single point interior to both. Touching doesn't count.
+pub fn segments_cross(
+    a1: SupportPoint,
+    a2: SupportPoint,
+    b1: SupportPoint,
+    b2: SupportPoint,
+) -> bool {
+    let d1 = orientation(b1, b2, a1);
+    let d2 = orientation(b1, b2, a2);
+    let d3 = orientation(a1, a2, b1);
+    let d4 = orientation(a1, a2, b2);
+    d1 * d2 < 0. && d3 * d4 < 0.
+}
+
+/// Reflects the velocity about a surface with unit normal `(nx, ny)`: `v - 2 (v . n) n`.
+pub fn reflect(vx: Real, vy: Real, nx: Real, ny: Real) -> (Real, Real) {
+    let d = 2. * (vx * nx + vy * ny);
+    (vx - d * nx, vy - d * ny)
+}
+
+/// Closest point to `(px, py)` on the boundary of an origin-centred ellipse with semi-axes
+/// `rx` and `ry`, found iteratively by following the evolute. From the centre the nearest
+/// points are the ends of the minor axis, and `(rx, 0)` is returned for a circle.
+pub fn closest_pnt_ellipse(rx: Real, ry: Real, px: Real, py: Real) -> (Real, Real) {
+    let (ax, ay) = (px.abs(), py.abs());
+    if ax == 0. && ay == 0. {
+        return if rx <= ry { (rx, 0.) } else { (0., ry) };
+    }
+    let mut tx = consts::FRAC_1_SQRT_2;
+    let mut ty = consts::FRAC_1_SQRT_2;
+    for _ in 0..4 {
+        let x = rx * tx;
+        let y = ry * ty;
+        let ex = (rx * rx - ry * ry) * tx * tx * tx / rx;
+        let ey = (ry * ry - rx * rx) * ty * ty * ty / ry;
+        let r = ((x - ex) * (x - ex) + (y - ey) * (y - ey)).sqrt();
+        let q = ((ax - ex) * (ax - ex) + (ay - ey) * (ay - ey)).sqrt();
+        tx = ((ax - ex) * r / q + ex) / rx;
+        ty = ((ay - ey) * r / q + ey) / ry;
+        tx = tx.clamp(0., 1.);
+        ty = ty.clamp(0., 1.);
+        let t = (tx * tx + ty * ty).sqrt();
+        tx /= t;
+        ty /= t;
+    }
+    ((rx * tx).copysign(px), (ry * ty).copysign(py))
+}
+
+/// Distance along the unit ray `o + t d` to where it first meets the circle, for an origin
+/// outside the circle. A tangent ray counts as a hit.
+pub fn ray_circle(o: SupportPoint, d: SupportPoint, c: SupportPoint, r: Real) -> Option<Distance> {
+    let m = o - c;
+    let b = nalgebra::dot(&m, &d);
+    let disc = b * b - (nalgebra::dot(&m, &m) - r * r);
+    if disc < 0. {
+        return None;
+    }
+    let t = -b - disc.sqrt();
+    if t >= 0. {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+/// Distance along the unit ray `o + t d` to where it leaves the circle, if that's ahead of
+/// the origin.
+pub fn ray_circle_exit(
+    o: SupportPoint,
+    d: SupportPoint,
+    c: SupportPoint,
+    r: Real,
+) -> Option<Distance> {
+    let m = o - c;
+    let b = nalgebra::dot(&m, &d);
+    let disc = b * b - (nalgebra::dot(&m, &m) - r * r);
+    if disc < 0. {
+        return None;
+    }
+    let t = -b + disc.sqrt();
+    if t >= 0. {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+/// Distance along the unit ray `o + t d` to where it first meets segment `p q`, endpoints
+/// included. A collinear ray hits the nearer endpoint in front of it.
+pub fn ray_segment(
+    o: SupportPoint,
+    d: SupportPoint,
+    p: SupportPoint,
+    q: SupportPoint,
+) -> Option<Distance> {
+    let e = q - p;
+    let op = p - o;
+    let denom = d.x * e.y - d.y * e.x;
+    if denom == 0. {
+        if op.x * d.y - op.y * d.x != 0. {
+            return None;
+        }
+        let tp = nalgebra::dot(&op, &d);
+        let tq = nalgebra::dot(&(q - o), &d);
+        return match (tp >= 0., tq >= 0.) {
+            (true, true) => Some(tp.min(tq)),
+            (true, false) => Some(tp),
+            (false, true) => Some(tq),
+            (false, false) => None,
+        };
+    }
+    let t = (op.x * e.y - op.y * e.x) / denom;
+    let u = (op.x * d.y - op.y * d.x) / denom;
+    if t >= 0. && (0. ..=1.).contains(&u) {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+fn vert(vertx: &[Real], verty: &[Real], i: usize) -> SupportPoint {
+    SupportPoint::new(vertx[i], verty[i])
+}
+
+/// Drops repeated and collinear vertices from a ring of indices.
+fn strip_collinear(vertx: &[Real], verty: &[Real], ring: &mut Vec<usize>) {
+    let mut i = 0;
+    while ring.len() > 3 && i < ring.len() {
+        let n = ring.len();
+        let a = vert(vertx, verty, ring[(i + n - 1) % n]);
+        let b = vert(vertx, verty, ring[i]);
+        let c = vert(vertx, verty, ring[(i + 1) % n]);
+        if orientation(a, b, c) == 0. {
+            ring.remove(i);
+            i = i.saturating_sub(1);
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// True if no turn along the counter-clockwise ring goes clockwise.
+fn ring_is_convex(vertx: &[Real], verty: &[Real], ring: &[usize]) -> bool {
+    let n = ring.len();
+    (0..n).all(|i| {
+        orientation(
+            vert(vertx, verty, ring[i]),
+            vert(vertx, verty, ring[(i + 1) % n]),
+            vert(vertx, verty, ring[(i + 2) % n]),
+        ) >= 0.
+    })
+}
+
+fn ear_clip(vertx: &[Real], verty: &[Real], mut ring: Vec<usize>) -> Vec<Vec<usize>> {
+    let mut pieces = vec![];
+    while ring.len() > 3 {
+        let n = ring.len();
+        let ear = (0..n).find(|&i| {
+            let a = vert(vertx, verty, ring[(i + n - 1) % n]);
+            let b = vert(vertx, verty, ring[i]);
+            let c = vert(vertx, verty, ring[(i + 1) % n]);
+            orientation(a, b, c) > 0.
+                && ring.iter().all(|&k| {
+                    let p = vert(vertx, verty, k);
+                    p == a
+                        || p == b
+                        || p == c
+                        || orientation(a, b, p) < 0.
+                        || orientation(b, c, p) < 0.
+                        || orientation(c, a, p) < 0.
+                })
+        });
+        match ear {
+            Some(i) => {
+                pieces.push(vec![ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]]);
+                ring.remove(i);
+            }
+            // Self-intersecting input has no ear left; keep what remains as one piece.
+            None => break,
+        }
+    }
+    pieces.push(ring);
+    pieces
+}
+
+/// Joins two rings along their shared edge, if they have one and the result is convex.
+fn merge_rings(vertx: &[Real], verty: &[Real], a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    let (na, nb) = (a.len(), b.len());
+    for i in 0..na {
+        let (p, q) = (a[i], a[(i + 1) % na]);
+        if let Some(j) = (0..nb).find(|&j| b[j] == q && b[(j + 1) % nb] == p) {
+            // a from q round to p, then b's vertices strictly between p and q.
+            let mut merged: Vec<usize> = (0..na).map(|k| a[(i + 1 + k) % na]).collect();
+            merged.extend((2..nb).map(|k| b[(j + k) % nb]));
+            if ring_is_convex(vertx, verty, &merged) {
+                return Some(merged);
+            }
+            return None;
+        }
+    }
+    None
+}
+
+/// The polygon's vertex indices wound counter-clockwise, without repeated or collinear
+/// vertices.
+fn ccw_ring(nvert: usize, vertx: &[Real], verty: &[Real]) -> Vec<usize> {
+    let mut ring: Vec<usize> = (0..nvert).collect();
+    ring.dedup_by(|a, b| vert(vertx, verty, *a) == vert(vertx, verty, *b));
+    if ring.len() > 1 && vert(vertx, verty, ring[0]) == vert(vertx, verty, ring[ring.len() - 1]) {
+        ring.pop();
+    }
+    strip_collinear(vertx, verty, &mut ring);
+    let ringx: Vec<Real> = ring.iter().map(|&k| vertx[k]).collect();
+    let ringy: Vec<Real> = ring.iter().map(|&k| verty[k]).collect();
+    if poly_signed_area(ring.len(), &ringx, &ringy) < 0. {
+        ring.reverse();
+    }
+    ring
+}
+
+/// Splits a simple polygon into counter-clockwise triangles. Convex polygons are fanned from
+/// their first vertex, anything else is ear clipped.
+pub fn triangulate_poly(nvert: usize, vertx: &[Real], verty: &[Real]) -> Vec<[(Real, Real); 3]> {
+    let ring = ccw_ring(nvert, vertx, verty);
+    if ring.len() < 3 {
+        return vec![];
+    }
+    let pieces = if ring_is_convex(vertx, verty, &ring) {
+        vec![ring]
+    } else {
+        ear_clip(vertx, verty, ring)
+    };
+    let point = |k: usize| (vertx[k], verty[k]);
+    let mut triangles = vec![];
+    for piece in pieces {
+        // Only a self-intersecting leftover has more than three vertices; fan it as is.
+        for i in 1..piece.len() - 1 {
+            triangles.push([point(piece[0]), point(piece[i]), point(piece[i + 1])]);
+        }
+    }
+    triangles
+}
+
+/// Splits a simple polygon into convex pieces by ear clipping and then merging neighbouring
+/// triangles back together while they stay convex (Hertel-Mehlhorn). Each piece is wound
+/// counter-clockwise; a convex input comes back as a single piece.
+pub fn decompose_convex_poly(
+    nvert: usize,
+    vertx: &[Real],
+    verty: &[Real],
+) -> Vec<(Vec<Real>, Vec<Real>)> {
+    let ring = ccw_ring(nvert, vertx, verty);
+    let mut pieces = if ring.len() <= 3 || ring_is_convex(vertx, verty, &ring) {
+        vec![ring]
+    } else {
+        ear_clip(vertx, verty, ring)
+    };
+    let mut merged = true;
+    while merged {
+        merged = false;
+        'search: for i in 0..pieces.len() {
+            for j in i + 1..pieces.len() {
+                if let Some(ring) = merge_rings(vertx, verty, &pieces[i], &pieces[j]) {
+                    pieces[i] = ring;
+                    pieces.swap_remove(j);
+                    merged = true;
+                    break 'search;
+                }
+            }
+        }
+    }
+
+    pieces
+        .into_iter()
+        .map(|mut ring| {
+            strip_collinear(vertx, verty, &mut ring);
+            (
+                ring.iter().map(|&k| vertx[k]).collect(),
+                ring.iter().map(|&k| verty[k]).collect(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closest_pnt_ellipse_from_the_centre() {
+        assert_eq!(closest_pnt_ellipse(2., 2., 0., 0.), (2., 0.));
+        assert_eq!(closest_pnt_ellipse(3., 1., 0., 0.), (0., 1.));
+        assert_eq!(closest_pnt_ellipse(1., 3., 0., 0.), (1., 0.));
+    }
+
+    #[test]
+    fn closest_pnt_ellipse_off_the_centre() {
+        let (x, y) = closest_pnt_ellipse(2., 2., 3., 4.);
+        assert!((x - 1.2).abs() < 1e-4 && (y - 1.6).abs() < 1e-4);
+        let (x, y) = closest_pnt_ellipse(3., 1., -5., 0.);
+        assert!((x + 3.).abs() < 1e-4 && y.abs() < 1e-4);
+        let (x, y) = closest_pnt_ellipse(3., 1., 1., 2.);
+        assert!((sqrf(x / 3.) + sqrf(y) - 1.).abs() < 1e-3);
+        assert!(x > 0. && y > 0.);
+    }
+
+    fn normals(vertx: &[Real], verty: &[Real]) -> Vec<(Real, Real)> {
+        let n = vertx.len();
+        let (nx, ny) = (calc_normx(n, verty), calc_normy(n, vertx));
+        nx.into_iter().zip(ny).collect()
+    }
+
+    #[test]
+    fn ccw_normals_point_outward_and_are_as_long_as_their_edge() {
+        // Counter-clockwise 2x1 rectangle: bottom, right, top, left.
+        let n = normals(&[0., 2., 2., 0.], &[0., 0., 1., 1.]);
+        assert_eq!(n, vec![(0., -2.), (1., 0.), (0., 2.), (-1., 0.)]);
+        let n = normals(&[0., 3., 0.], &[0., 0., 4.]);
+        assert_eq!(n, vec![(0., -3.), (4., 3.), (-4., 0.)]);
+        assert!((dist_sqrdf(0., 0., n[1].0, n[1].1).sqrt() - 5.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cw_normals_are_flipped() {
+        let n = normals(&[0., 0., 2., 2.], &[0., 1., 1., 0.]);
+        // Left, top, right, bottom, now all pointing inward.
+        assert_eq!(n, vec![(1., 0.), (0., -2.), (-1., 0.), (0., 2.)]);
+    }
+
+    #[test]
+    fn zero_length_edges_get_a_zero_normal() {
+        let n = normals(&[0., 1., 1., 0.], &[0., 0., 0., 1.]);
+        assert_eq!(n[1], (0., 0.));
+        assert!(n.iter().all(|&(x, y)| x.is_finite() && y.is_finite()));
+        assert_eq!(normals(&[2.], &[3.]), vec![(0., 0.)]);
+        assert!(normals(&[], &[]).is_empty());
+    }
+}
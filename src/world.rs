@@ -0,0 +1,978 @@
+use std::cmp::Ordering;
+use std::cmp::Ordering::Equal;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use std::thread;
+
+use aabb::Aabb;
+use collidable::{Collidable, RayHit};
+use Real;
+
+/// A change in whether a pair of ids is colliding, from `CollisionWorld::step_events`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CollisionEvent {
+    Enter(usize, usize),
+    Stay(usize, usize),
+    Exit(usize, usize),
+}
+
+/// One edge of a collidable's outline, from `CollisionWorld::debug_lines`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DebugLine {
+    pub id: usize,
+    pub collidable_type: u8,
+    pub start: (Real, Real),
+    pub end: (Real, Real),
+}
+
+/// A collidable borrowed from a `CollisionWorld` by `get_mut`. Derefs to the `Collidable`;
+/// dropping it reindexes a changed `collidable_type`.
+pub struct CollidableMut<'a> {
+    world: &'a mut CollisionWorld,
+    id: usize,
+    old_type: u8,
+}
+
+impl<'a> Deref for CollidableMut<'a> {
+    type Target = Collidable;
+
+    fn deref(&self) -> &Collidable {
+        self.world.slots[self.id]
+            .as_deref()
+            .expect("borrowed slot is occupied")
+    }
+}
+
+impl<'a> DerefMut for CollidableMut<'a> {
+    fn deref_mut(&mut self) -> &mut Collidable {
+        Arc::make_mut(
+            self.world.slots[self.id]
+                .as_mut()
+                .expect("borrowed slot is occupied"),
+        )
+    }
+}
+
+impl<'a> Drop for CollidableMut<'a> {
+    fn drop(&mut self) {
+        let (id, old_type) = (self.id, self.old_type);
+        let new_type = self.collidable_type;
+        // Only write when needed, so a shape shared with a snapshot isn't copied.
+        if self.collidable_id != id {
+            self.collidable_id = id;
+        }
+        if new_type != old_type {
+            self.world.unindex_type(id, old_type);
+            self.world.index_type(id, new_type);
+        }
+    }
+}
+
+/// A saved copy of a `CollisionWorld`, for rollback. Cheap to take, see `CollisionWorld`.
+#[derive(Clone, Debug)]
+pub struct WorldSnapshot {
+    world: CollisionWorld,
+}
+
+/// Owns a set of collidables and finds the colliding pairs among them.
+///
+/// Ids index straight into the world. Removing a collidable frees its slot without shifting
+/// the others, and the slot is reused by a later `insert`.
+///
+/// Collidables are shared copy-on-write, so cloning the world or taking a `snapshot` only
+/// copies pointers; a shape is copied the first time it's changed through `get_mut`.
+#[derive(Clone, Debug, Default)]
+pub struct CollisionWorld {
+    slots: Vec<Option<Arc<Collidable>>>,
+    free: Vec<usize>,
+    disabled_types: HashSet<(u8, u8)>,
+    previous_pairs: Vec<(usize, usize)>,
+    removed_pairs: Vec<(usize, usize)>,
+    by_type: HashMap<u8, Vec<usize>>,
+    velocities: HashMap<usize, (Real, Real)>,
+}
+
+impl CollisionWorld {
+    pub fn new() -> CollisionWorld {
+        CollisionWorld {
+            slots: vec![],
+            free: vec![],
+            disabled_types: HashSet::new(),
+            previous_pairs: vec![],
+            removed_pairs: vec![],
+            by_type: HashMap::new(),
+            velocities: HashMap::new(),
+        }
+    }
+
+    /// Stores `collidable` and returns its id, which is also written to its `collidable_id`.
+    pub fn insert(&mut self, mut collidable: Collidable) -> usize {
+        let id = match self.free.pop() {
+            Some(id) => id,
+            None => {
+                self.slots.push(None);
+                self.slots.len() - 1
+            }
+        };
+        collidable.collidable_id = id;
+        self.index_type(id, collidable.collidable_type);
+        self.slots[id] = Some(Arc::new(collidable));
+        id
+    }
+
+    pub fn remove(&mut self, id: usize) -> Option<Collidable> {
+        let removed = self
+            .slots
+            .get_mut(id)
+            .and_then(|slot| slot.take())
+            .map(Arc::unwrap_or_clone);
+        if let Some(ref c) = removed {
+            self.free.push(id);
+            self.unindex_type(id, c.collidable_type);
+            self.velocities.remove(&id);
+            // Queue the exits now, so a later insert into the same slot gets a fresh Enter.
+            let (gone, kept): (Vec<_>, Vec<_>) = self
+                .previous_pairs
+                .iter()
+                .partition(|&&(a, b)| a == id || b == id);
+            self.removed_pairs.extend(gone);
+            self.previous_pairs = kept;
+        }
+        removed
+    }
+
+    /// Replaces the collidable at `id` and returns the old one. Does nothing if `id` is free.
+    pub fn update(&mut self, id: usize, mut collidable: Collidable) -> Option<Collidable> {
+        match self.slots.get_mut(id) {
+            Some(slot) if slot.is_some() => {
+                collidable.collidable_id = id;
+                let new_type = collidable.collidable_type;
+                let old = slot.replace(Arc::new(collidable)).map(Arc::unwrap_or_clone);
+                if let Some(ref old) = old {
+                    if old.collidable_type != new_type {
+                        self.unindex_type(id, old.collidable_type);
+                        self.index_type(id, new_type);
+                    }
+                }
+                old
+            }
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Collidable> {
+        self.slots.get(id).and_then(|slot| slot.as_deref())
+    }
+
+    /// Write access to the collidable at `id`. The type index is brought up to date when the
+    /// guard is dropped, and `collidable_id` is put back to `id` if it was changed.
+    pub fn get_mut(&mut self, id: usize) -> Option<CollidableMut<'_>> {
+        let old_type = self.get(id)?.collidable_type;
+        Some(CollidableMut {
+            world: self,
+            id,
+            old_type,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Collidable> {
+        self.slots.iter().filter_map(|slot| slot.as_deref())
+    }
+
+    /// Every collidable's `outline`, tagged with its id and type so a renderer can colour
+    /// by type.
+    pub fn debug_lines(&self) -> Vec<DebugLine> {
+        let mut lines = vec![];
+        for c in self.iter() {
+            lines.extend(c.outline().into_iter().map(|(start, end)| DebugLine {
+                id: c.collidable_id,
+                collidable_type: c.collidable_type,
+                start,
+                end,
+            }));
+        }
+        lines
+    }
+
+    /// Captures the collidables, their ids, the collision matrix and the event state.
+    pub fn snapshot(&self) -> WorldSnapshot {
+        WorldSnapshot {
+            world: self.clone(),
+        }
+    }
+
+    /// Puts the world back exactly as it was when `snap` was taken.
+    pub fn restore(&mut self, snap: &WorldSnapshot) {
+        *self = snap.world.clone();
+    }
+
+    /// The collidables of one `collidable_type`, in id order, from an index kept by
+    /// `insert`, `update`, `get_mut` and `remove`.
+    pub fn iter_type(&self, t: u8) -> impl Iterator<Item = &Collidable> {
+        self.by_type
+            .get(&t)
+            .into_iter()
+            .flatten()
+            .filter_map(move |&id| self.get(id))
+    }
+
+    /// The collidable of type `t` with the lowest id.
+    pub fn first_of_type(&self, t: u8) -> Option<&Collidable> {
+        self.iter_type(t).next()
+    }
+
+    fn index_type(&mut self, id: usize, t: u8) {
+        let ids = self.by_type.entry(t).or_default();
+        if let Err(at) = ids.binary_search(&id) {
+            ids.insert(at, id);
+        }
+    }
+
+    fn unindex_type(&mut self, id: usize, t: u8) {
+        if let Some(ids) = self.by_type.get_mut(&t) {
+            if let Ok(at) = ids.binary_search(&id) {
+                ids.remove(at);
+            }
+        }
+    }
+
+    /// Sets how far `id` moves over the next step. The broadphase sweeps its box along that
+    /// motion, so `candidate_pairs` and `impact_pairs` see what it passes on the way. Does
+    /// nothing if `id` is free.
+    pub fn set_velocity(&mut self, id: usize, vx: Real, vy: Real) {
+        if self.get(id).is_none() {
+            return;
+        }
+        if vx == 0. && vy == 0. {
+            self.velocities.remove(&id);
+        } else {
+            self.velocities.insert(id, (vx, vy));
+        }
+    }
+
+    /// The velocity last given to `id` with `set_velocity`, zero by default.
+    pub fn velocity(&self, id: usize) -> (Real, Real) {
+        self.velocities.get(&id).cloned().unwrap_or((0., 0.))
+    }
+
+    /// Every colliding pair of ids, lowest id first, skipping pairs of types disabled with
+    /// `set_collision_matrix`. Candidates come from a sweep and prune over the x extents of
+    /// the bounding boxes and are then checked with `collides`.
+    ///
+    /// Each pair appears once and the list is sorted, so the result only depends on the
+    /// shapes and their ids, never on insertion order.
+    pub fn pairs(&self) -> Vec<(usize, usize)> {
+        self.pairs_filtered(&|a, b| self.layers_collide(a, b))
+    }
+
+    /// Like `pairs`, but only pairs whose `collidable_type`s pass `mask` are tested. The
+    /// collision matrix is ignored.
+    pub fn pairs_filtered(&self, mask: &dyn Fn(u8, u8) -> bool) -> Vec<(usize, usize)> {
+        self.sweep(&|a, b| mask(a.collidable_type, b.collidable_type) && a.collides(b))
+    }
+
+    /// The pairs whose swept bounding boxes overlap, lowest id first, skipping pairs of
+    /// types disabled with `set_collision_matrix`. No narrowphase check is done.
+    pub fn candidate_pairs(&self) -> Vec<(usize, usize)> {
+        self.sweep(&|a, b| self.layers_collide(a.collidable_type, b.collidable_type))
+    }
+
+    /// The candidate pairs that touch during the step, with the `time_of_impact` of each
+    /// under the velocities from `set_velocity`. Pairs already colliding have a time of 0.
+    pub fn impact_pairs(&self) -> Vec<(usize, usize, Real)> {
+        self.candidate_pairs()
+            .into_iter()
+            .filter_map(|(a, b)| {
+                let (ca, cb) = (self.get(a)?, self.get(b)?);
+                let toi = ca.time_of_impact(self.velocity(a), cb, self.velocity(b))?;
+                Some((a, b, toi))
+            })
+            .collect()
+    }
+
+    fn sweep(&self, accept: &dyn Fn(&Collidable, &Collidable) -> bool) -> Vec<(usize, usize)> {
+        let boxes = self.sorted_boxes();
+        let mut pairs = vec![];
+        for i in 0..boxes.len() {
+            sweep_from(&boxes, i, accept, &mut pairs);
+        }
+        pairs.sort();
+        pairs
+    }
+
+    /// The same pairs as `pairs`, in the same order, with the sweep split across `threads` scoped
+    /// threads. Worth it for large worlds, where the narrowphase checks dominate. This uses
+    /// `std::thread::scope` rather than an optional `rayon` feature so the crate keeps
+    /// nalgebra as its only required dependency, and the caller picks the thread count.
+    pub fn pairs_parallel(&self, threads: usize) -> Vec<(usize, usize)> {
+        let boxes = self.sorted_boxes();
+        let threads = threads.clamp(1, boxes.len().max(1));
+        let accept = |a: &Collidable, b: &Collidable| {
+            self.layers_collide(a.collidable_type, b.collidable_type) && a.collides(b)
+        };
+        let mut pairs: Vec<(usize, usize)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|t| {
+                    let (boxes, accept) = (&boxes, &accept);
+                    // Interleaved so the long sweeps from the left aren't all on one thread.
+                    scope.spawn(move || {
+                        let mut pairs = vec![];
+                        for i in (t..boxes.len()).step_by(threads) {
+                            sweep_from(boxes, i, accept, &mut pairs);
+                        }
+                        pairs
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|w| w.join().expect("pair worker panicked"))
+                .collect()
+        });
+        pairs.sort();
+        pairs
+    }
+
+    fn sorted_boxes(&self) -> Vec<(usize, &Collidable, Aabb)> {
+        let mut boxes: Vec<(usize, &Collidable, Aabb)> = self
+            .iter()
+            .map(|c| {
+                let b = margin_box(c);
+                let (vx, vy) = self.velocity(c.collidable_id);
+                let moved = Aabb::new(b.min_x + vx, b.min_y + vy, b.max_x + vx, b.max_y + vy);
+                (c.collidable_id, c, b.merge(&moved))
+            })
+            .collect();
+        boxes.sort_by(|a, b| a.2.min_x.partial_cmp(&b.2.min_x).unwrap_or(Equal));
+        boxes
+    }
+
+    /// Compares the current `pairs` with those from the previous call: new pairs `Enter`,
+    /// continuing ones `Stay` and ended ones `Exit`. Pairs with a collidable removed since
+    /// the last call exit first; the rest follow in pair order.
+    pub fn step_events(&mut self) -> Vec<CollisionEvent> {
+        let current = self.pairs();
+        let mut events: Vec<CollisionEvent> = self
+            .removed_pairs
+            .drain(..)
+            .map(|(a, b)| CollisionEvent::Exit(a, b))
+            .collect();
+        let (mut i, mut j) = (0, 0);
+        let previous = &self.previous_pairs;
+        while i < previous.len() || j < current.len() {
+            if j == current.len() || (i < previous.len() && previous[i] < current[j]) {
+                events.push(CollisionEvent::Exit(previous[i].0, previous[i].1));
+                i += 1;
+            } else if i == previous.len() || current[j] < previous[i] {
+                events.push(CollisionEvent::Enter(current[j].0, current[j].1));
+                j += 1;
+            } else {
+                events.push(CollisionEvent::Stay(current[j].0, current[j].1));
+                i += 1;
+                j += 1;
+            }
+        }
+        self.previous_pairs = current;
+        events
+    }
+
+    /// Enables or disables collisions between two `collidable_type`s, in both directions.
+    /// Every pair of types collides until disabled.
+    pub fn set_collision_matrix(&mut self, type_a: u8, type_b: u8, enabled: bool) {
+        let key = (type_a.min(type_b), type_a.max(type_b));
+        if enabled {
+            self.disabled_types.remove(&key);
+        } else {
+            self.disabled_types.insert(key);
+        }
+    }
+
+    pub fn layers_collide(&self, type_a: u8, type_b: u8) -> bool {
+        !self
+            .disabled_types
+            .contains(&(type_a.min(type_b), type_a.max(type_b)))
+    }
+
+    /// The `k` collidables nearest to `(x, y)` as `(id, distance)`, nearest first, with ties
+    /// broken by id. A point inside a shape is at distance 0. Bounding boxes go into a heap
+    /// by their distance, built in O(n), and exact distances are only computed while a box
+    /// could still beat the current `k`th nearest, so a query visiting `m` shapes costs
+    /// O(n + m log n).
+    pub fn nearest_k(&self, x: Real, y: Real, k: usize) -> Vec<(usize, Real)> {
+        if k == 0 {
+            return vec![];
+        }
+        let mut candidates: BinaryHeap<Nearer> = self
+            .iter()
+            .map(|c| {
+                Nearer(
+                    Aabb::from(c.aabb()).distance_to_point(x, y),
+                    c.collidable_id,
+                )
+            })
+            .collect();
+
+        let mut nearest: Vec<(usize, Real)> = vec![];
+        while let Some(Nearer(bound, id)) = candidates.pop() {
+            if nearest.len() == k && bound > nearest[k - 1].1 {
+                break;
+            }
+            let d = self
+                .get(id)
+                .map_or(Real::INFINITY, |c| point_distance(c, x, y));
+            let at = nearest
+                .iter()
+                .position(|&(other, e)| d < e || (d == e && id < other))
+                .unwrap_or(nearest.len());
+            nearest.insert(at, (id, d));
+            nearest.truncate(k);
+        }
+        nearest
+    }
+
+    /// The nearest hit within `max_dist` along the ray, skipping collidables for which
+    /// `filter` returns false. See `Collidable::raycast` for rays starting inside a shape.
+    pub fn raycast<F>(
+        &self,
+        ox: Real,
+        oy: Real,
+        dx: Real,
+        dy: Real,
+        max_dist: Real,
+        filter: F,
+    ) -> Option<(usize, RayHit)>
+    where
+        F: Fn(&Collidable) -> bool,
+    {
+        self.ray_hits(ox, oy, dx, dy, max_dist, filter)
+            .into_iter()
+            .min_by(|a, b| a.1.distance.partial_cmp(&b.1.distance).unwrap_or(Equal))
+    }
+
+    /// Every hit within `max_dist` along the ray, nearest first.
+    pub fn raycast_all<F>(
+        &self,
+        ox: Real,
+        oy: Real,
+        dx: Real,
+        dy: Real,
+        max_dist: Real,
+        filter: F,
+    ) -> Vec<(usize, RayHit)>
+    where
+        F: Fn(&Collidable) -> bool,
+    {
+        let mut hits = self.ray_hits(ox, oy, dx, dy, max_dist, filter);
+        hits.sort_by(|a, b| a.1.distance.partial_cmp(&b.1.distance).unwrap_or(Equal));
+        hits
+    }
+
+    /// Whether the segment from `a` to `b` is clear of every collidable whose type isn't
+    /// `ignore`d. A shape touching the segment short of `b` blocks it, as does one containing
+    /// `a`, so ignore the seeker's own type. Shapes that only touch at `b` don't block.
+    pub fn line_of_sight(
+        &self,
+        ax: Real,
+        ay: Real,
+        bx: Real,
+        by: Real,
+        ignore: &dyn Fn(u8) -> bool,
+    ) -> bool {
+        let (dx, dy) = (bx - ax, by - ay);
+        let len = (dx * dx + dy * dy).sqrt();
+        if len == 0. {
+            return true;
+        }
+        self.ray_hits(ax, ay, dx, dy, len, |c| !ignore(c.collidable_type))
+            .iter()
+            .all(|hit| hit.1.distance >= len)
+    }
+
+    fn ray_hits<F>(
+        &self,
+        ox: Real,
+        oy: Real,
+        dx: Real,
+        dy: Real,
+        max_dist: Real,
+        filter: F,
+    ) -> Vec<(usize, RayHit)>
+    where
+        F: Fn(&Collidable) -> bool,
+    {
+        let len = (dx * dx + dy * dy).sqrt();
+        if len == 0. {
+            return vec![];
+        }
+        let (ux, uy) = (dx / len, dy / len);
+        let mut hits = vec![];
+        for c in self.iter().filter(|c| filter(c)) {
+            match Aabb::from(c.aabb()).ray_entry(ox, oy, ux, uy) {
+                Some(t) if t <= max_dist => {}
+                _ => continue,
+            }
+            if let Some(hit) = c.raycast(ox, oy, ux, uy) {
+                if hit.distance <= max_dist {
+                    hits.push((c.collidable_id, hit));
+                }
+            }
+        }
+        hits
+    }
+}
+
+fn point_distance(c: &Collidable, x: Real, y: Real) -> Real {
+    if c.contains_point(x, y) {
+        return 0.;
+    }
+    let (px, py) = c.nearest_point(x, y);
+    ((px - x) * (px - x) + (py - y) * (py - y)).sqrt()
+}
+
+/// Every colliding pair in `shapes`, as slice indices with the lower first, each pair once
+/// and in order. Runs the same sweep and prune as `CollisionWorld::pairs`, without the
+/// collision matrix, for when a world is more than is needed.
+pub fn find_overlaps<T>(shapes: &[Collidable<T>]) -> Vec<(usize, usize)> {
+    let mut boxes: Vec<(usize, &Collidable<T>, Aabb)> = shapes
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (i, c, margin_box(c)))
+        .collect();
+    boxes.sort_by(|a, b| a.2.min_x.partial_cmp(&b.2.min_x).unwrap_or(Equal));
+    let mut pairs = vec![];
+    for i in 0..boxes.len() {
+        sweep_from(
+            &boxes,
+            i,
+            &|a: &Collidable<T>, b: &Collidable<T>| a.collides(b),
+            &mut pairs,
+        );
+    }
+    pairs.sort();
+    pairs
+}
+
+/// A box distance and id, ordered so a `BinaryHeap` pops the nearest box first.
+struct Nearer(Real, usize);
+
+impl PartialEq for Nearer {
+    fn eq(&self, other: &Nearer) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Nearer {}
+
+impl PartialOrd for Nearer {
+    fn partial_cmp(&self, other: &Nearer) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Nearer {
+    fn cmp(&self, other: &Nearer) -> Ordering {
+        other
+            .0
+            .partial_cmp(&self.0)
+            .unwrap_or(Equal)
+            .then(other.1.cmp(&self.1))
+    }
+}
+
+/// The bounding box grown by the collidable's `margin`.
+fn margin_box<T>(c: &Collidable<T>) -> Aabb {
+    let (min_x, min_y, max_x, max_y) = c.aabb();
+    let m = c.margin;
+    Aabb::new(min_x - m, min_y - m, max_x + m, max_y + m)
+}
+
+/// Pushes the pairs between `boxes[i]` and the boxes after it that overlap and pass `accept`,
+/// reporting each by the keys stored with the boxes.
+fn sweep_from<T, F>(
+    boxes: &[(usize, &Collidable<T>, Aabb)],
+    i: usize,
+    accept: &F,
+    pairs: &mut Vec<(usize, usize)>,
+) where
+    F: Fn(&Collidable<T>, &Collidable<T>) -> bool + ?Sized,
+{
+    let (ida, ca, ref a) = boxes[i];
+    for &(idb, cb, ref b) in &boxes[i + 1..] {
+        if b.min_x > a.max_x {
+            break;
+        }
+        // NOTE: touching boxes are kept, segments collide on contact.
+        if b.min_y > a.max_y || a.min_y > b.max_y {
+            continue;
+        }
+        if accept(ca, cb) {
+            pairs.push((ida.min(idb), ida.max(idb)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small LCG so the scenes are random but the same on every run.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> Real {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (self.0 >> 40) as Real / (1u64 << 24) as Real
+        }
+
+        fn range(&mut self, lo: Real, hi: Real) -> Real {
+            lo + (hi - lo) * self.next()
+        }
+    }
+
+    fn random_world(n: usize, seed: u64) -> CollisionWorld {
+        let mut rng = Lcg(seed);
+        let mut world = CollisionWorld::new();
+        for i in 0..n {
+            let (x, y) = (rng.range(0., 1000.), rng.range(0., 1000.));
+            let size = rng.range(1., 12.);
+            let c = match i % 3 {
+                0 => Collidable::new_circle(0, 0, x, y, size * 0.5),
+                1 => Collidable::new_rect(1, 0, x, y, size, rng.range(1., 12.)),
+                _ => Collidable::new_regular(2, 0, x, y, size * 0.5, 5, rng.range(0., 6.)),
+            };
+            world.insert(c);
+        }
+        world
+    }
+
+    #[test]
+    fn parallel_pairs_match_serial_pairs() {
+        let world = random_world(5000, 1);
+        let serial = world.pairs();
+        assert!(!serial.is_empty());
+        for &threads in &[1, 3, 8] {
+            assert_eq!(world.pairs_parallel(threads), serial);
+        }
+        assert_eq!(CollisionWorld::new().pairs_parallel(4), vec![]);
+    }
+
+    #[test]
+    fn nearest_k_orders_a_known_layout() {
+        let mut world = CollisionWorld::new();
+        let far = world.insert(Collidable::new_circle(0, 0, 20., 0., 1.));
+        let near = world.insert(Collidable::new_circle(0, 0, 3., 0., 1.));
+        let around = world.insert(Collidable::new_rect(0, 0, -1., -1., 2., 2.));
+        let mid = world.insert(Collidable::new_rect(0, 0, 0., 6., 2., 2.));
+        let tie = world.insert(Collidable::new_circle(0, 0, -3., 0., 1.));
+        let ids: Vec<usize> = world.nearest_k(0., 0., 10).iter().map(|n| n.0).collect();
+        assert_eq!(ids, vec![around, near, tie, mid, far]);
+        let two = world.nearest_k(0., 0., 2);
+        assert_eq!(two, vec![(around, 0.), (near, 2.)]);
+        assert_eq!(world.nearest_k(0., 0., 3)[2], (tie, 2.));
+        assert!(world.nearest_k(0., 0., 0).is_empty());
+        assert!(CollisionWorld::new().nearest_k(0., 0., 3).is_empty());
+    }
+
+    #[test]
+    fn nearest_k_matches_a_full_scan() {
+        let world = random_world(500, 7);
+        let mut all: Vec<(usize, Real)> = world
+            .iter()
+            .map(|c| (c.collidable_id, point_distance(c, 500., 500.)))
+            .collect();
+        all.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap().then(a.0.cmp(&b.0)));
+        all.truncate(25);
+        assert_eq!(world.nearest_k(500., 500., 25), all);
+    }
+
+    #[test]
+    fn a_wall_blocks_sight_but_a_gap_does_not() {
+        let mut world = CollisionWorld::new();
+        // A wall at x = 5 with a gap between y = 2 and y = 4.
+        world.insert(Collidable::new_rect(1, 0, 5., -10., 0.5, 12.));
+        world.insert(Collidable::new_rect(1, 0, 5., 4., 0.5, 10.));
+        world.insert(Collidable::new_circle(2, 0, 0., 0., 0.5));
+        let none = |_: u8| false;
+        let seeker = |t: u8| t == 2;
+        assert!(!world.line_of_sight(0., 0., 10., 0., &seeker));
+        assert!(world.line_of_sight(0., 3., 10., 3., &seeker));
+        assert!(world.line_of_sight(0., 0., 10., 6., &seeker));
+        // The seeker's own circle contains the start, so it only passes when ignored.
+        assert!(!world.line_of_sight(0., 3., 0., 0., &none));
+        assert!(world.line_of_sight(0., 3., 0., 0., &seeker));
+        // Ignoring the wall's type sees straight through it.
+        assert!(world.line_of_sight(1., 0., 10., 0., &|t: u8| t == 1));
+        assert!(world.line_of_sight(2., 2., 2., 2., &none));
+    }
+
+    #[test]
+    fn pairs_do_not_depend_on_insertion_order() {
+        let shapes: Vec<Collidable> = random_world(300, 3).iter().cloned().collect();
+        let mut rng = Lcg(11);
+        let mut reference: Option<Vec<(usize, usize)>> = None;
+        for _ in 0..4 {
+            let mut order: Vec<usize> = (0..shapes.len()).collect();
+            for i in (1..order.len()).rev() {
+                order.swap(i, (rng.next() * (i + 1) as Real) as usize % (i + 1));
+            }
+            let mut world = CollisionWorld::new();
+            let mut original = vec![0; shapes.len()];
+            for &k in &order {
+                original[world.insert(shapes[k].clone())] = k;
+            }
+            let pairs = world.pairs();
+            assert!(pairs.windows(2).all(|w| w[0] < w[1]));
+            assert!(pairs.iter().all(|&(a, b)| a < b));
+            // Slots filled in a fixed order give the same ids whatever order shapes arrive in.
+            let mut fixed = CollisionWorld::new();
+            for _ in 0..shapes.len() {
+                fixed.insert(Collidable::new_circle(0, 0, -1e4, -1e4, 0.));
+            }
+            for &k in &order {
+                fixed.update(k, shapes[k].clone());
+            }
+            let fixed = fixed.pairs();
+            let mut mapped: Vec<(usize, usize)> = pairs
+                .iter()
+                .map(|&(a, b)| (original[a].min(original[b]), original[a].max(original[b])))
+                .collect();
+            mapped.sort();
+            assert_eq!(mapped, fixed);
+            match reference {
+                Some(ref r) => assert_eq!(&fixed, r),
+                None => reference = Some(fixed),
+            }
+        }
+        assert!(!reference.unwrap().is_empty());
+    }
+
+    #[test]
+    fn get_mut_keeps_the_type_index_current() {
+        let mut world = CollisionWorld::new();
+        let a = world.insert(Collidable::new_circle(1, 0, 0., 0., 1.));
+        let b = world.insert(Collidable::new_circle(1, 0, 5., 0., 1.));
+        world.get_mut(a).unwrap().translate(1., 0.);
+        assert_eq!(world.get(a).unwrap().centrex, 1.);
+        assert_eq!(world.iter_type(1).count(), 2);
+        {
+            let mut c = world.get_mut(b).unwrap();
+            c.collidable_type = 2;
+            c.collidable_id = 99;
+        }
+        let ones: Vec<usize> = world.iter_type(1).map(|c| c.collidable_id).collect();
+        let twos: Vec<usize> = world.iter_type(2).map(|c| c.collidable_id).collect();
+        assert_eq!((ones, twos), (vec![a], vec![b]));
+        assert!(world.get_mut(7).is_none());
+        // A snapshot keeps its own copy of a shape changed afterwards.
+        let snap = world.snapshot();
+        world.get_mut(a).unwrap().collidable_type = 2;
+        assert_eq!(world.iter_type(2).count(), 2);
+        world.restore(&snap);
+        assert_eq!(world.first_of_type(1).map(|c| c.centrex), Some(1.));
+    }
+
+    #[test]
+    fn velocity_pairs_a_fast_mover_with_what_lies_in_its_path() {
+        let mut world = CollisionWorld::new();
+        let bullet = world.insert(Collidable::new_circle(0, 0, 0., 0., 0.2));
+        let wall = world.insert(Collidable::new_rect(0, 0, 50., -5., 0.5, 10.));
+        world.insert(Collidable::new_rect(0, 0, 50., 20., 0.5, 10.));
+        assert!(world.candidate_pairs().is_empty());
+        world.set_velocity(bullet, 100., 0.);
+        assert_eq!(world.velocity(bullet), (100., 0.));
+        assert_eq!(world.candidate_pairs(), vec![(bullet, wall)]);
+        // Candidates aren't collisions, but the sweep finds the time of impact.
+        assert!(world.pairs().is_empty());
+        let impacts = world.impact_pairs();
+        assert_eq!(impacts.len(), 1);
+        assert_eq!((impacts[0].0, impacts[0].1), (bullet, wall));
+        assert!((impacts[0].2 - 0.498).abs() < 1e-3);
+        world.set_velocity(bullet, 0., 0.);
+        assert!(world.candidate_pairs().is_empty());
+    }
+
+    #[test]
+    fn masked_out_types_overlap_without_pairing() {
+        let mut world = CollisionWorld::new();
+        let player = world.insert(Collidable::new_circle(1, 0, 0., 0., 1.));
+        let ghost = world.insert(Collidable::new_circle(2, 0, 0.5, 0., 1.));
+        let wall = world.insert(Collidable::new_rect(3, 0, 0.5, -1., 1., 2.));
+        assert_eq!(
+            world.pairs(),
+            vec![(player, ghost), (player, wall), (ghost, wall)]
+        );
+
+        world.set_collision_matrix(2, 1, false);
+        assert!(!world.layers_collide(1, 2) && !world.layers_collide(2, 1));
+        assert!(world.layers_collide(1, 3));
+        assert_eq!(world.pairs(), vec![(player, wall), (ghost, wall)]);
+        assert_eq!(world.pairs_parallel(2), world.pairs());
+        assert!(!world.candidate_pairs().contains(&(player, ghost)));
+        assert!(world
+            .step_events()
+            .iter()
+            .all(|e| *e != CollisionEvent::Enter(player, ghost)));
+
+        world.set_collision_matrix(1, 2, true);
+        assert_eq!(world.pairs().len(), 3);
+    }
+
+    #[test]
+    fn a_circle_passing_through_another_enters_then_exits() {
+        let mut world = CollisionWorld::new();
+        let target = world.insert(Collidable::new_circle(0, 0, 10., 0., 1.));
+        let mover = world.insert(Collidable::new_circle(0, 0, 0., 0., 1.));
+        let mut events = vec![];
+        for step in 0..20 {
+            let x = step as Real;
+            world.update(mover, Collidable::new_circle(0, 0, x, 0., 1.));
+            events.extend(world.step_events());
+        }
+        // Overlapping strictly while 8 < x < 12: entering at 9, staying for 10 and 11.
+        assert_eq!(
+            events,
+            vec![
+                CollisionEvent::Enter(target, mover),
+                CollisionEvent::Stay(target, mover),
+                CollisionEvent::Stay(target, mover),
+                CollisionEvent::Exit(target, mover),
+            ]
+        );
+    }
+
+    #[test]
+    fn removing_a_collidable_mid_frame_emits_its_exit() {
+        let mut world = CollisionWorld::new();
+        let a = world.insert(Collidable::new_circle(0, 0, 0., 0., 1.));
+        let b = world.insert(Collidable::new_circle(0, 0, 1., 0., 1.));
+        let c = world.insert(Collidable::new_circle(0, 0, -1., 0., 1.));
+        assert_eq!(
+            world.step_events(),
+            vec![CollisionEvent::Enter(a, b), CollisionEvent::Enter(a, c)]
+        );
+        world.remove(b);
+        // The slot is reused, but the new shape's contact is a fresh Enter.
+        let d = world.insert(Collidable::new_circle(0, 0, 0.5, 0., 1.));
+        assert_eq!(d, b);
+        assert_eq!(
+            world.step_events(),
+            vec![
+                CollisionEvent::Exit(a, b),
+                CollisionEvent::Enter(a, d),
+                CollisionEvent::Stay(a, c),
+                CollisionEvent::Enter(d, c),
+            ]
+        );
+        world.remove(c);
+        assert_eq!(
+            world.step_events(),
+            vec![
+                CollisionEvent::Exit(a, c),
+                CollisionEvent::Exit(d, c),
+                CollisionEvent::Stay(a, d),
+            ]
+        );
+        assert_eq!(world.step_events(), vec![CollisionEvent::Stay(a, d)]);
+    }
+
+    #[test]
+    fn restoring_a_snapshot_brings_back_the_earlier_geometry() {
+        let mut world = random_world(50, 9);
+        for id in 0..10 {
+            world.get_mut(id).unwrap().translate(3., -2.);
+        }
+        let saved: Vec<Collidable> = world.iter().cloned().collect();
+        let pairs = world.pairs();
+        let snap = world.snapshot();
+
+        for id in 0..50 {
+            let mut c = world.get_mut(id).unwrap();
+            c.rotate(0.4);
+            c.translate(-7., 5.);
+        }
+        world.remove(3);
+        world.set_collision_matrix(0, 1, false);
+        assert!(!world.iter().zip(&saved).all(|(a, b)| a.approx_eq(b, 1e-4)));
+
+        world.restore(&snap);
+        assert_eq!(world.len(), saved.len());
+        assert!(world
+            .iter()
+            .zip(&saved)
+            .all(|(a, b)| a.approx_eq(b, 0.) && a.collidable_id == b.collidable_id));
+        assert_eq!(world.pairs(), pairs);
+        assert!(world.layers_collide(0, 1));
+    }
+
+    #[test]
+    fn debug_lines_for_two_rects() {
+        let mut world = CollisionWorld::new();
+        let a = world.insert(Collidable::new_rect(1, 0, 0., 0., 2., 1.));
+        let b = world.insert(Collidable::new_rect(2, 0, 5., 5., 1., 1.));
+        let lines = world.debug_lines();
+        assert_eq!(lines.len(), 8);
+        assert!(lines[..4]
+            .iter()
+            .all(|l| l.id == a && l.collidable_type == 1));
+        assert!(lines[4..]
+            .iter()
+            .all(|l| l.id == b && l.collidable_type == 2));
+        assert_eq!((lines[0].start, lines[0].end), ((0., 0.), (2., 0.)));
+        // Each outline is closed.
+        assert_eq!(lines[3].end, lines[0].start);
+        assert_eq!(lines[7].end, lines[4].start);
+    }
+
+    #[test]
+    fn find_overlaps_matches_a_brute_force_scan() {
+        for seed in 0..4 {
+            let mut rng = Lcg(seed);
+            let shapes: Vec<Collidable> = (0..400)
+                .map(|i| {
+                    let (x, y) = (rng.range(0., 200.), rng.range(0., 200.));
+                    let size = rng.range(1., 12.);
+                    let mut c = match i % 4 {
+                        0 => Collidable::new_circle(0, i, x, y, size * 0.5),
+                        1 => Collidable::new_rect(0, i, x, y, size, rng.range(1., 12.)),
+                        2 => Collidable::new_capsule(
+                            0,
+                            i,
+                            x,
+                            y,
+                            x + size,
+                            y + rng.range(-5., 5.),
+                            1.,
+                        ),
+                        _ => Collidable::new_regular(0, i, x, y, size * 0.5, 5, rng.range(0., 6.)),
+                    };
+                    if i % 7 == 0 {
+                        c.margin = 1.;
+                    }
+                    c
+                })
+                .collect();
+            let mut expected = vec![];
+            for i in 0..shapes.len() {
+                for j in i + 1..shapes.len() {
+                    if shapes[i].collides(&shapes[j]) {
+                        expected.push((i, j));
+                    }
+                }
+            }
+            assert!(!expected.is_empty());
+            assert_eq!(find_overlaps(&shapes), expected, "seed {}", seed);
+        }
+    }
+}